@@ -0,0 +1,54 @@
+use std::env;
+use std::process;
+
+use tiny_http::Server;
+use tttable::server::{self, Session};
+use tttable::TtPlaythrough;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:8080";
+
+/// usage: `tttable-server SESSION.json [--address HOST:PORT] [--players N]`,
+/// serves the JSON API for the session file, which is started with
+/// `--players N` players (4 by default) if it doesn't exist yet
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flag = |flag: &str| {
+        let index = args.iter().position(|arg| arg == flag)?;
+        args.get(index + 1).map(String::as_str)
+    };
+
+    let Some(path) = args.first().filter(|arg| !arg.starts_with("--")) else {
+        eprintln!("usage: tttable-server SESSION.json [--address HOST:PORT] [--players N]");
+        process::exit(1);
+    };
+    let players = match flag("--players").map(str::parse) {
+        Some(Ok(count)) => count,
+        Some(Err(_)) => {
+            eprintln!("--players needs a number");
+            process::exit(1);
+        }
+        None => 4,
+    };
+    let address = flag("--address").unwrap_or(DEFAULT_ADDRESS);
+
+    let mut session = Session::open(path, || {
+        TtPlaythrough::new((0..players).collect(), 2).unwrap_or_else(|error| {
+            eprintln!("{error}");
+            process::exit(1);
+        })
+    })
+    .unwrap_or_else(|error| {
+        eprintln!("can't open {path}: {error}");
+        process::exit(1);
+    });
+
+    let server = Server::http(address).unwrap_or_else(|error| {
+        eprintln!("can't listen on {address}: {error}");
+        process::exit(1);
+    });
+
+    println!("Serving {path} on http://{address}");
+    server::serve(&server, &mut session);
+}
@@ -0,0 +1,544 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::tt::{TtError, TtMatch, TtPlaythrough};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketKind {
+    Single,
+    /// losers drop into a losers bracket, its winner meets the winners
+    /// bracket's winner in a single grand final
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Part {
+    Winners,
+    Losers,
+    GrandFinal,
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::Winners => write!(f, "Winners"),
+            Part::Losers => write!(f, "Losers"),
+            Part::GrandFinal => write!(f, "Grand final"),
+        }
+    }
+}
+
+/// Where a bracket match's player comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// index into the seeds, 0 is the top seed
+    Seed(usize),
+    Bye,
+    /// index into `Bracket::matches`
+    WinnerOf(usize),
+    LoserOf(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entrant {
+    Player(usize),
+    Bye,
+}
+
+impl fmt::Display for Entrant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entrant::Player(player) => write!(f, "{player}"),
+            Entrant::Bye => write!(f, "bye"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketMatch {
+    pub part: Part,
+    /// 1-based within the part
+    pub round: usize,
+    pub sources: [Source; 2],
+    /// the entrants once their sources are decided
+    pub entrants: [Option<Entrant>; 2],
+    /// the match with sides once both entrants are players
+    pub tt_match: Option<TtMatch>,
+    pub winner: Option<Entrant>,
+    pub loser: Option<Entrant>,
+}
+
+impl BracketMatch {
+    fn new(part: Part, round: usize, sources: [Source; 2]) -> Self {
+        Self {
+            part,
+            round,
+            sources,
+            entrants: [None, None],
+            tt_match: None,
+            winner: None,
+            loser: None,
+        }
+    }
+
+    /// both players known and not played yet
+    pub fn is_ready(&self) -> bool {
+        self.tt_match.is_some() && self.winner.is_none()
+    }
+}
+
+/// Single or double elimination bracket. Byes go to the top seeds, every
+/// match gets its sides as soon as both players are known.
+#[derive(Debug, Clone)]
+pub struct Bracket {
+    playthrough: TtPlaythrough,
+    seeds: Vec<usize>,
+    matches: Vec<BracketMatch>,
+}
+
+impl Bracket {
+    /// `seeds` lists the players from the top seed down, the playthrough's
+    /// matches count as history for the sides. Fails if a seed doesn't take
+    /// part in the playthrough or is listed twice.
+    pub fn new(
+        playthrough: TtPlaythrough,
+        seeds: Vec<usize>,
+        kind: BracketKind,
+    ) -> Result<Self, TtError> {
+        if let Some(seed) =
+            seeds.iter().find(|s| !playthrough.players().contains(s))
+        {
+            return Err(TtError::UnknownPlayer(*seed));
+        }
+        if let Some(seed) = seeds.iter().duplicates().next() {
+            return Err(TtError::InvalidConfiguration(format!(
+                "seed {seed} is listed twice"
+            )));
+        }
+        if seeds.len() < 2 {
+            return Err(TtError::InvalidConfiguration(
+                "at least 2 seeds are needed".to_string(),
+            ));
+        }
+
+        let size = seeds.len().next_power_of_two().max(2);
+        let slot = |seed: usize| {
+            if seed < seeds.len() {
+                Source::Seed(seed)
+            } else {
+                Source::Bye
+            }
+        };
+
+        let mut matches = vec![];
+        let mut add_round =
+            |part: Part, round: usize, sources: Vec<[Source; 2]>| {
+                let first = matches.len();
+                matches.extend(
+                    sources
+                        .into_iter()
+                        .map(|s| BracketMatch::new(part, round, s)),
+                );
+                (first..matches.len()).collect_vec()
+            };
+
+        let first_round = seed_order(size)
+            .into_iter()
+            .tuples()
+            .map(|(a, b)| [slot(a), slot(b)])
+            .collect_vec();
+        let mut winners_rounds = vec![add_round(Part::Winners, 1, first_round)];
+
+        while winners_rounds.last().unwrap().len() > 1 {
+            let sources =
+                pair_up(winners_rounds.last().unwrap(), Source::WinnerOf);
+            winners_rounds.push(add_round(
+                Part::Winners,
+                winners_rounds.len() + 1,
+                sources,
+            ));
+        }
+
+        let winners_final = winners_rounds.last().unwrap()[0];
+        if kind == BracketKind::Single {
+            return Ok(Self::with_matches(playthrough, seeds, matches));
+        }
+
+        // the losers of the first round play each other, after that the
+        // losers bracket alternates between taking in the losers of the next
+        // winners round and halving itself
+        let mut losers_final = Source::LoserOf(winners_final);
+        let mut losers_round = 0;
+        let mut previous = vec![];
+
+        for (index, winners_round) in winners_rounds.iter().enumerate() {
+            let mut dropped = winners_round
+                .iter()
+                .map(|id| Source::LoserOf(*id))
+                .collect_vec();
+
+            let sources = if index == 0 {
+                if dropped.len() < 2 {
+                    break;
+                }
+                dropped.into_iter().tuples().map(|(a, b)| [a, b]).collect()
+            } else {
+                // mixed up so that rematches come as late as possible
+                if index % 2 == 1 {
+                    dropped.reverse();
+                }
+                previous
+                    .iter()
+                    .zip(dropped)
+                    .map(|(id, loser)| [Source::WinnerOf(*id), loser])
+                    .collect_vec()
+            };
+
+            losers_round += 1;
+            previous = add_round(Part::Losers, losers_round, sources);
+
+            if index > 0 && previous.len() > 1 {
+                let sources = pair_up(&previous, Source::WinnerOf);
+                losers_round += 1;
+                previous = add_round(Part::Losers, losers_round, sources);
+            }
+
+            losers_final = Source::WinnerOf(previous[0]);
+        }
+
+        add_round(
+            Part::GrandFinal,
+            1,
+            vec![[Source::WinnerOf(winners_final), losers_final]],
+        );
+
+        Ok(Self::with_matches(playthrough, seeds, matches))
+    }
+
+    fn with_matches(
+        playthrough: TtPlaythrough,
+        seeds: Vec<usize>,
+        matches: Vec<BracketMatch>,
+    ) -> Self {
+        let mut bracket = Self {
+            playthrough,
+            seeds,
+            matches,
+        };
+        bracket.resolve();
+        bracket
+    }
+
+    pub fn matches(&self) -> &[BracketMatch] {
+        &self.matches
+    }
+
+    /// the matches which can be played now
+    pub fn ready_matches(&self) -> Vec<&TtMatch> {
+        self.matches
+            .iter()
+            .filter(|m| m.is_ready())
+            .filter_map(|m| m.tt_match.as_ref())
+            .collect_vec()
+    }
+
+    /// the winner of the final once it's played
+    pub fn champion(&self) -> Option<usize> {
+        match self.matches.last()?.winner? {
+            Entrant::Player(player) => Some(player),
+            Entrant::Bye => None,
+        }
+    }
+
+    /// Records the winner of their ready match and advances the players.
+    /// Returns `false` if `winner` has no ready match.
+    pub fn record_result(&mut self, winner: usize) -> bool {
+        let Some(bracket_match) = self.matches.iter_mut().find(|m| {
+            m.is_ready()
+                && m.tt_match.as_ref().is_some_and(|t| t.has_player(winner))
+        }) else {
+            return false;
+        };
+
+        let tt_match = bracket_match.tt_match.clone().unwrap();
+        let loser = if tt_match.left() == winner {
+            tt_match.right()
+        } else {
+            tt_match.left()
+        };
+        bracket_match.winner = Some(Entrant::Player(winner));
+        bracket_match.loser = Some(Entrant::Player(loser));
+
+        self.playthrough
+            .force_match((tt_match.left(), tt_match.right()))
+            .expect("the seeds take part in the playthrough");
+        self.resolve();
+
+        true
+    }
+
+    /// fills in entrants until nothing changes, byes advance the other
+    /// entrant right away
+    fn resolve(&mut self) {
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for id in 0..self.matches.len() {
+                let entrants =
+                    self.matches[id].sources.map(|source| self.entrant(source));
+
+                let bracket_match = &self.matches[id];
+                if bracket_match.entrants != entrants {
+                    self.matches[id].entrants = entrants;
+                    changed = true;
+                }
+
+                let bracket_match = &self.matches[id];
+                if bracket_match.winner.is_some()
+                    || bracket_match.tt_match.is_some()
+                {
+                    continue;
+                }
+
+                match entrants {
+                    [Some(Entrant::Player(a)), Some(Entrant::Player(b))] => {
+                        let tt_match = self.assign_sides(a, b);
+                        self.matches[id].tt_match = Some(tt_match);
+                        changed = true;
+                    }
+                    [Some(a), Some(b)] => {
+                        let winner = if a == Entrant::Bye { b } else { a };
+                        self.matches[id].winner = Some(winner);
+                        self.matches[id].loser = Some(Entrant::Bye);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn entrant(&self, source: Source) -> Option<Entrant> {
+        match source {
+            Source::Seed(seed) => Some(Entrant::Player(self.seeds[seed])),
+            Source::Bye => Some(Entrant::Bye),
+            Source::WinnerOf(id) => self.matches[id].winner,
+            Source::LoserOf(id) => self.matches[id].loser,
+        }
+    }
+
+    /// The orientation breaking the fewest of:
+    /// - rule 4: the same sides as when the two last met
+    /// - rule 3: a player on the same side as in their own last game
+    ///
+    /// then the one evening out the side counts. `a` is the higher seed
+    /// (or waited longer) and plays left if it's still a tie.
+    fn assign_sides(&self, a: usize, b: usize) -> TtMatch {
+        let matches = self.playthrough.matches();
+        let side_counts = self.playthrough.side_counts();
+
+        let last_game =
+            |player: usize| matches.iter().rev().find(|m| m.has_player(player));
+
+        let score = |(left, right): (usize, usize)| {
+            let same_sides_again = matches
+                .iter()
+                .rev()
+                .find(|m| m.check_same_players((left, right)))
+                .is_some_and(|m| m.left() == left);
+
+            let same_side = last_game(left).is_some_and(|m| m.left() == left)
+                as usize
+                + last_game(right).is_some_and(|m| m.right() == right) as usize;
+
+            let (left_l, left_r) = side_counts[&left];
+            let (right_l, right_r) = side_counts[&right];
+            let unbalance = (left_l as i64 - left_r as i64)
+                + (right_r as i64 - right_l as i64);
+
+            (same_sides_again, same_side, unbalance)
+        };
+
+        let (left, right) = [(a, b), (b, a)]
+            .into_iter()
+            .min_by_key(|players| score(*players))
+            .unwrap();
+
+        TtMatch::new(left, right)
+    }
+}
+
+impl fmt::Display for Bracket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let source = |source: &Source, entrant: &Option<Entrant>| match (
+            entrant, source,
+        ) {
+            (Some(entrant), _) => entrant.to_string(),
+            (None, Source::WinnerOf(id)) => format!("winner of #{}", id + 1),
+            (None, Source::LoserOf(id)) => format!("loser of #{}", id + 1),
+            (None, _) => "?".to_string(),
+        };
+
+        for (part, round) in
+            self.matches.iter().map(|m| (m.part, m.round)).dedup()
+        {
+            writeln!(f, "{part} round {round}")?;
+
+            for (id, m) in self.matches.iter().enumerate() {
+                if (m.part, m.round) != (part, round) {
+                    continue;
+                }
+
+                let players = match &m.tt_match {
+                    Some(tt_match) => tt_match.to_string(),
+                    None => format!(
+                        "{} vs {}",
+                        source(&m.sources[0], &m.entrants[0]),
+                        source(&m.sources[1], &m.entrants[1])
+                    ),
+                };
+
+                match m.winner {
+                    Some(winner) => {
+                        writeln!(f, "  #{} {players} won by {winner}", id + 1)?
+                    }
+                    None => writeln!(f, "  #{} {players}", id + 1)?,
+                }
+            }
+        }
+
+        if let Some(champion) = self.champion() {
+            writeln!(f, "Champion: {champion}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// bracket positions of the seeds so that the top seeds meet last, e.g.
+/// 0 7 3 4 1 6 2 5 for 8
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+
+    while order.len() < size {
+        let length = order.len() * 2;
+        order = order
+            .into_iter()
+            .flat_map(|seed| [seed, length - 1 - seed])
+            .collect_vec();
+    }
+
+    order
+}
+
+fn pair_up(
+    ids: &[usize],
+    source: impl Fn(usize) -> Source,
+) -> Vec<[Source; 2]> {
+    ids.iter()
+        .tuples()
+        .map(|(a, b)| [source(*a), source(*b)])
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// plays every ready match, the better seed (lower number) wins
+    fn play_out(bracket: &mut Bracket) -> usize {
+        let mut played = 0;
+
+        while let Some(tt_match) = bracket.ready_matches().first().cloned() {
+            let winner = tt_match.left().min(tt_match.right());
+            assert!(bracket.record_result(winner));
+            played += 1;
+        }
+
+        played
+    }
+
+    #[test]
+    fn test_new_checks_the_seeds() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+
+        let error =
+            Bracket::new(playthrough.clone(), vec![0, 5], BracketKind::Single)
+                .unwrap_err();
+        assert_eq!(error, TtError::UnknownPlayer(5));
+
+        let error =
+            Bracket::new(playthrough, vec![0, 1, 0], BracketKind::Single)
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid configuration: seed 0 is listed twice"
+        );
+    }
+
+    #[test]
+    fn test_seed_order() {
+        assert_eq!(seed_order(2), vec![0, 1]);
+        assert_eq!(seed_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+    }
+
+    #[test]
+    fn test_single_elimination_with_byes() {
+        let players = (0..5).collect_vec();
+        let playthrough = TtPlaythrough::new(players.clone(), 2).unwrap();
+        let mut bracket =
+            Bracket::new(playthrough, players, BracketKind::Single).unwrap();
+
+        // the top three seeds get a bye, so 1 and 2 already meet in the
+        // second round
+        let ready = bracket.ready_matches();
+        assert_eq!(ready.len(), 2);
+        assert!(ready[0].has_player(3) && ready[0].has_player(4));
+        assert!(ready[1].has_player(1) && ready[1].has_player(2));
+
+        assert!(!bracket.record_result(0));
+        assert_eq!(play_out(&mut bracket), 4);
+        assert_eq!(bracket.champion(), Some(0));
+    }
+
+    #[test]
+    fn test_double_elimination() {
+        let players = (0..8).collect_vec();
+        let playthrough = TtPlaythrough::new(players.clone(), 2).unwrap();
+        let mut bracket =
+            Bracket::new(playthrough, players, BracketKind::Double).unwrap();
+
+        // 7 + 6 matches in the two brackets and the grand final
+        assert_eq!(bracket.matches().len(), 14);
+        assert_eq!(play_out(&mut bracket), 14);
+        assert_eq!(bracket.champion(), Some(0));
+
+        let grand_final = bracket.matches().last().unwrap();
+        assert_eq!(grand_final.loser, Some(Entrant::Player(1)));
+    }
+
+    #[test]
+    fn test_sides_follow_the_history() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+        let mut bracket =
+            Bracket::new(playthrough, vec![0, 1, 2], BracketKind::Single)
+                .unwrap();
+
+        // 0 has a bye, 1 played left before and switches to the right
+        assert_eq!(bracket.ready_matches(), vec![&TtMatch::new(1, 2)]);
+        bracket.record_result(1);
+        assert_eq!(bracket.ready_matches(), vec![&TtMatch::new(0, 1)]);
+
+        // the grand final is a rematch on the other sides
+        let playthrough = TtPlaythrough::new(vec![0, 1], 2).unwrap();
+        let mut bracket =
+            Bracket::new(playthrough, vec![0, 1], BracketKind::Double).unwrap();
+
+        assert_eq!(bracket.ready_matches(), vec![&TtMatch::new(0, 1)]);
+        bracket.record_result(0);
+        assert_eq!(bracket.ready_matches(), vec![&TtMatch::new(1, 0)]);
+    }
+}
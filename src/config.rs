@@ -0,0 +1,530 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::recommend::RecommendWeights;
+use crate::tt::{Constraint, GamesLimit, Rule, RuleSet, TtPlaythrough};
+
+/// How the matches of a configured session are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// the players who sat out the longest first, like `generate`
+    MostRested,
+    /// the top recommendation, like `generate --fair`
+    Fair,
+    /// the top recommendation without dead ends, like `generate --lookahead`
+    Lookahead,
+    RoundRobin,
+    DoubleRoundRobin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    Log,
+    Markdown,
+    Html,
+    Svg,
+    Pdf,
+}
+
+/// A session described in a TOML file, e.g.
+///
+/// ```toml
+/// players = ["Alice", "Bob", "Carol", "Dave"]
+///
+/// [rules]
+/// max_games = 2
+/// window = 4
+/// max_rest = 2
+/// soft = { 4 = 1 }
+///
+/// [generator]
+/// strategy = "fair"
+/// matches = 20
+///
+/// [output]
+/// format = "markdown"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// names in player order, the first is player 0
+    pub players: Vec<String>,
+    #[serde(default)]
+    pub rules: RulesConfig,
+    #[serde(default)]
+    pub generator: GeneratorConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulesConfig {
+    /// rule 4, the only rule which can be switched off
+    #[serde(default = "default_rule_4")]
+    pub rule_4: bool,
+    /// rule 2: at most `max_games` within any `window` matches, by default
+    /// in a row
+    #[serde(default = "default_max_games")]
+    pub max_games: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<usize>,
+    /// rule 5, off if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rest: Option<usize>,
+    /// weights of the rules made soft by their number, e.g. `{ 3 = 10 }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub soft: BTreeMap<String, u32>,
+    /// rule 2 limits of single players
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub limits: Vec<PlayerLimit>,
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self {
+            rule_4: default_rule_4(),
+            max_games: default_max_games(),
+            window: None,
+            max_rest: None,
+            soft: BTreeMap::new(),
+            limits: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerLimit {
+    pub player: String,
+    pub max_games: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeneratorConfig {
+    #[serde(default = "default_strategy")]
+    pub strategy: Strategy,
+    /// ignored by the round robins
+    #[serde(default = "default_matches")]
+    pub matches: usize,
+    /// depth of the `lookahead` strategy
+    #[serde(default = "default_lookahead")]
+    pub lookahead: usize,
+    #[serde(default)]
+    pub weights: WeightsConfig,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            strategy: default_strategy(),
+            matches: default_matches(),
+            lookahead: default_lookahead(),
+            weights: WeightsConfig::default(),
+        }
+    }
+}
+
+/// `RecommendWeights` of the `fair` and `lookahead` strategies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightsConfig {
+    #[serde(default = "default_weight")]
+    pub rest: i64,
+    #[serde(default = "default_weight")]
+    pub side_balance: i64,
+    #[serde(default = "default_weight")]
+    pub pairing: i64,
+}
+
+impl Default for WeightsConfig {
+    fn default() -> Self {
+        Self {
+            rest: default_weight(),
+            side_balance: default_weight(),
+            pairing: default_weight(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    #[serde(default = "default_format")]
+    pub format: OutputFormat,
+    #[serde(default = "default_title")]
+    pub title: String,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            format: default_format(),
+            title: default_title(),
+        }
+    }
+}
+
+fn default_rule_4() -> bool {
+    true
+}
+
+fn default_max_games() -> usize {
+    2
+}
+
+fn default_strategy() -> Strategy {
+    Strategy::MostRested
+}
+
+fn default_matches() -> usize {
+    20
+}
+
+fn default_lookahead() -> usize {
+    3
+}
+
+fn default_weight() -> i64 {
+    1
+}
+
+fn default_format() -> OutputFormat {
+    OutputFormat::Log
+}
+
+fn default_title() -> String {
+    "Table tennis".to_string()
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(error) => {
+                write!(f, "can't read the config: {error}")
+            }
+            ConfigError::Parse(error) => write!(f, "invalid config: {error}"),
+            ConfigError::Invalid(message) => {
+                write!(f, "invalid config: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(ConfigError::Read)?;
+        Self::from_toml(&text)
+    }
+
+    /// parses and validates the config and fills in the defaults which
+    /// depend on other values
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let mut config: Config =
+            toml::from_str(text).map_err(ConfigError::Parse)?;
+
+        config.validate()?;
+
+        let rules = &mut config.rules;
+        rules.window.get_or_insert(rules.max_games + 1);
+        for limit in rules.limits.iter_mut() {
+            limit.window.get_or_insert(limit.max_games + 1);
+        }
+
+        Ok(config)
+    }
+
+    /// the config with all defaults as TOML, to embed into exports
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("the config is serializable")
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+        if self.players.len() < 2 {
+            return invalid("at least 2 players are needed".to_string());
+        }
+
+        let mut names = BTreeSet::new();
+        for name in self.players.iter() {
+            if name.trim().is_empty() {
+                return invalid("player names can't be empty".to_string());
+            }
+            if !names.insert(name) {
+                return invalid(format!("player \"{name}\" is listed twice"));
+            }
+        }
+
+        let rules = &self.rules;
+        check_games_limit("rules", rules.max_games, rules.window)?;
+
+        for limit in rules.limits.iter() {
+            if !names.contains(&limit.player) {
+                return invalid(format!(
+                    "rules.limits: unknown player \"{}\"",
+                    limit.player
+                ));
+            }
+            check_games_limit(
+                &format!("rules.limits of \"{}\"", limit.player),
+                limit.max_games,
+                limit.window,
+            )?;
+        }
+
+        if rules.max_rest == Some(0) {
+            return invalid("rules.max_rest must be at least 1".to_string());
+        }
+
+        for rule in rules.soft.keys() {
+            if soft_rule(rule).is_none() {
+                return invalid(format!(
+                    "rules.soft: \"{rule}\" is no rule number from 1 to {}",
+                    Rule::ALL.len()
+                ));
+            }
+        }
+        let soft_max_rest = rules
+            .soft
+            .keys()
+            .any(|rule| soft_rule(rule) == Some(Rule::MaxRest));
+        if soft_max_rest && rules.max_rest.is_none() {
+            return invalid(
+                "rules.soft: rule 5 needs rules.max_rest".to_string(),
+            );
+        }
+
+        let generator = &self.generator;
+        if generator.matches == 0 {
+            return invalid("generator.matches must be at least 1".to_string());
+        }
+        if generator.strategy == Strategy::Lookahead && generator.lookahead == 0
+        {
+            return invalid(
+                "generator.lookahead must be at least 1".to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn names(&self) -> BTreeMap<usize, String> {
+        self.players.iter().cloned().enumerate().collect()
+    }
+
+    pub fn weights(&self) -> RecommendWeights {
+        let weights = self.generator.weights;
+
+        RecommendWeights {
+            rest: weights.rest,
+            side_balance: weights.side_balance,
+            pairing: weights.pairing,
+        }
+    }
+
+    /// a playthrough of the players, numbered in the order of the config,
+    /// with its rules
+    pub fn playthrough(&self) -> TtPlaythrough {
+        let rules = &self.rules;
+        let player = |name: &String| {
+            self.players.iter().position(|p| p == name).unwrap()
+        };
+        let limit = |max_games: usize, window: Option<usize>| {
+            GamesLimit::within(max_games, window.unwrap_or(max_games + 1))
+        };
+
+        let mut rule_set = RuleSet::default();
+        for (rule, weight) in rules.soft.iter() {
+            let rule = soft_rule(rule).unwrap();
+            rule_set =
+                rule_set.with_constraint(rule, Constraint::Soft(*weight));
+        }
+        if !rules.rule_4 {
+            rule_set = rule_set.without_rule(Rule::SameSidesVsSameOpponent);
+        }
+
+        let mut playthrough = TtPlaythrough::new(
+            (0..self.players.len()).collect(),
+            rules.max_games,
+        )
+        .expect("the players and max_games are validated")
+        .with_rules(rule_set)
+        .with_default_games_limit(limit(rules.max_games, rules.window))
+        .expect("the games limits are validated");
+
+        for l in rules.limits.iter() {
+            playthrough = playthrough
+                .with_games_limit(
+                    player(&l.player),
+                    limit(l.max_games, l.window),
+                )
+                .expect("the games limits are validated");
+        }
+
+        if let Some(max_rest) = rules.max_rest {
+            playthrough = playthrough
+                .with_max_rest(max_rest)
+                .expect("max_rest is validated");
+        }
+
+        playthrough
+    }
+}
+
+fn soft_rule(number: &str) -> Option<Rule> {
+    Rule::from_number(number.parse().ok()?)
+}
+
+fn check_games_limit(
+    context: &str,
+    max_games: usize,
+    window: Option<usize>,
+) -> Result<(), ConfigError> {
+    if max_games == 0 {
+        return Err(ConfigError::Invalid(format!(
+            "{context}: max_games must be at least 1"
+        )));
+    }
+
+    if let Some(window) = window {
+        if window <= max_games {
+            return Err(ConfigError::Invalid(format!(
+                "{context}: window {window} must be larger than max_games \
+                 {max_games}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        players = ["Alice", "Bob", "Carol", "Dave"]
+
+        [rules]
+        rule_4 = false
+        max_games = 2
+        window = 4
+        max_rest = 2
+        soft = { 3 = 10 }
+        limits = [{ player = "Dave", max_games = 1 }]
+
+        [generator]
+        strategy = "fair"
+        weights = { rest = 2 }
+
+        [output]
+        format = "markdown"
+    "#;
+
+    fn error(text: &str) -> String {
+        Config::from_toml(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_config_playthrough() {
+        let config = Config::from_toml(EXAMPLE).unwrap();
+
+        assert_eq!(config.generator.strategy, Strategy::Fair);
+        assert_eq!(config.output.format, OutputFormat::Markdown);
+        assert_eq!(config.weights().rest, 2);
+        assert_eq!(config.weights().pairing, 1);
+        assert_eq!(config.names()[&3], "Dave");
+
+        let playthrough = config.playthrough();
+        assert_eq!(playthrough.players(), &[0, 1, 2, 3]);
+        assert_eq!(playthrough.games_limit(0), GamesLimit::within(2, 4));
+        assert_eq!(playthrough.games_limit(3), GamesLimit::consecutive(1));
+        assert_eq!(
+            playthrough.constraint(Rule::SameSide),
+            Some(Constraint::Soft(10))
+        );
+        assert_eq!(
+            playthrough.constraint(Rule::MaxRest),
+            Some(Constraint::Hard)
+        );
+        assert!(!playthrough.rules().contains(Rule::SameSidesVsSameOpponent));
+    }
+
+    #[test]
+    fn test_config_defaults_resolved() {
+        let config = Config::from_toml(r#"players = ["A", "B"]"#).unwrap();
+
+        assert!(config
+            .playthrough()
+            .rules()
+            .contains(Rule::SameSidesVsSameOpponent));
+        assert_eq!(config.rules.window, Some(3));
+        assert_eq!(config.generator.strategy, Strategy::MostRested);
+        assert_eq!(config.output.format, OutputFormat::Log);
+
+        // the resolved config loads to the same config again
+        let toml = config.to_toml();
+        assert!(toml.contains("window = 3"));
+        assert_eq!(Config::from_toml(&toml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert_eq!(
+            error(r#"players = ["A"]"#),
+            "invalid config: at least 2 players are needed"
+        );
+        assert_eq!(
+            error(r#"players = ["A", "A"]"#),
+            "invalid config: player \"A\" is listed twice"
+        );
+        assert_eq!(
+            error("players = [\"A\", \"B\"]\n[rules]\nmax_games = 0"),
+            "invalid config: rules: max_games must be at least 1"
+        );
+        assert_eq!(
+            error("players = [\"A\", \"B\"]\n[rules]\nwindow = 2"),
+            "invalid config: rules: window 2 must be larger than max_games 2"
+        );
+        assert_eq!(
+            error(
+                "players = [\"A\", \"B\"]\n[rules]\n\
+                 limits = [{ player = \"C\", max_games = 1 }]"
+            ),
+            "invalid config: rules.limits: unknown player \"C\""
+        );
+        assert_eq!(
+            error("players = [\"A\", \"B\"]\n[rules]\nsoft = { 6 = 1 }"),
+            "invalid config: rules.soft: \"6\" is no rule number from 1 to 5"
+        );
+        assert_eq!(
+            error("players = [\"A\", \"B\"]\n[rules]\nsoft = { 5 = 1 }"),
+            "invalid config: rules.soft: rule 5 needs rules.max_rest"
+        );
+
+        // typos and unknown values are reported by the parser
+        assert!(error("players = [\"A\", \"B\"]\n[rules]\nmax_game = 1")
+            .contains("unknown field `max_game`"));
+        assert!(error(
+            "players = [\"A\", \"B\"]\n[generator]\nstrategy = \"best\""
+        )
+        .contains("unknown variant `best`"));
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use itertools::Itertools;
+
+use crate::symmetry;
+use crate::tt::{TtMatch, TtPlaythrough, TtState};
+
+#[derive(Debug, Clone)]
+pub struct DotOptions {
+    /// merge states which only differ by the names of the players
+    pub collapse_symmetric: bool,
+    /// fill states without any possible next match
+    pub highlight_dead_ends: bool,
+    /// stop expanding the graph after this many states, the states reached
+    /// beyond are shown dashed without their successors
+    pub max_states: usize,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            collapse_symmetric: false,
+            highlight_dead_ends: true,
+            max_states: 10_000,
+        }
+    }
+}
+
+/// Exports every state reachable from the playthrough's current history as a
+/// Graphviz digraph, render it with e.g. `dot -Tsvg rules.dot > rules.svg`.
+///
+/// Nodes are the summarized histories (see `TtState`), edges are the matches
+/// allowed by the rules in that state.
+pub fn export_dot(playthrough: &TtPlaythrough, options: &DotOptions) -> String {
+    let graph = explore(playthrough, options);
+
+    let mut dot = String::from("digraph tttable {\n");
+    dot += "    rankdir=LR;\n";
+    dot += "    node [shape=box, fontname=\"monospace\"];\n";
+
+    for (id, state) in graph.states.iter().enumerate() {
+        let mut attributes = vec![format!("label=\"{state}\"")];
+
+        if options.highlight_dead_ends && graph.dead_ends.contains(&id) {
+            attributes.push("style=filled".to_string());
+            attributes.push("fillcolor=\"#f4a6a6\"".to_string());
+        } else if graph.truncated.contains(&id) {
+            attributes.push("style=dashed".to_string());
+        }
+
+        dot += &format!("    s{id} [{}];\n", attributes.join(", "));
+    }
+
+    for ((from, to), matches) in graph.edges.iter() {
+        let label = matches
+            .iter()
+            .map(|(m, penalty)| match penalty {
+                0 => format!("{} - {}", m.left(), m.right()),
+                _ => format!("{} - {} (+{penalty})", m.left(), m.right()),
+            })
+            .join("\\n");
+
+        dot += &format!("    s{from} -> s{to} [label=\"{label}\"];\n");
+    }
+
+    dot += "}\n";
+
+    dot
+}
+
+struct StateGraph {
+    states: Vec<TtState>,
+    /// the matches leading from one state to another with their penalty
+    edges: BTreeMap<(usize, usize), Vec<(TtMatch, u32)>>,
+    dead_ends: BTreeSet<usize>,
+    /// states beyond `max_states`, their successors aren't explored
+    truncated: BTreeSet<usize>,
+}
+
+fn explore(playthrough: &TtPlaythrough, options: &DotOptions) -> StateGraph {
+    let mut graph = StateGraph {
+        states: vec![],
+        edges: BTreeMap::new(),
+        dead_ends: BTreeSet::new(),
+        truncated: BTreeSet::new(),
+    };
+    let mut ids: BTreeMap<TtState, usize> = BTreeMap::new();
+    let mut queue: VecDeque<(usize, TtPlaythrough)> = VecDeque::new();
+
+    let (start_state, start) = representative(playthrough, options);
+    ids.insert(start_state.clone(), 0);
+    graph.states.push(start_state);
+    queue.push_back((0, start));
+
+    while let Some((id, current)) = queue.pop_front() {
+        let mut has_next = false;
+
+        for players in current.candidate_matches() {
+            if !current.check_match_possible(players) {
+                continue;
+            }
+            has_next = true;
+            let penalty = current.match_penalty(players);
+
+            let mut next = current.clone();
+            next.play_match(players).expect("checked above");
+            let (next_state, next) = representative(&next, options);
+
+            let next_id = match ids.get(&next_state) {
+                Some(next_id) => *next_id,
+                None => {
+                    let next_id = graph.states.len();
+                    if next_id < options.max_states {
+                        queue.push_back((next_id, next));
+                    } else {
+                        graph.truncated.insert(next_id);
+                    }
+                    ids.insert(next_state.clone(), next_id);
+                    graph.states.push(next_state);
+                    next_id
+                }
+            };
+
+            graph
+                .edges
+                .entry((id, next_id))
+                .or_default()
+                .push((TtMatch::new(players.0, players.1), penalty));
+        }
+
+        if !has_next {
+            graph.dead_ends.insert(id);
+        }
+    }
+
+    graph
+}
+
+/// the playthrough itself or, when collapsing symmetric states, the player
+/// relabeling of it with the smallest state
+fn representative(
+    playthrough: &TtPlaythrough,
+    options: &DotOptions,
+) -> (TtState, TtPlaythrough) {
+    if !options.collapse_symmetric {
+        return (playthrough.state(), playthrough.clone());
+    }
+
+    let symmetries = symmetry::respecting_rules(
+        symmetry::symmetries(playthrough.players(), false),
+        playthrough,
+    );
+
+    symmetries
+        .iter()
+        .map(|s| {
+            let relabeled = playthrough.relabeled(s.mapping());
+
+            (relabeled.state(), relabeled)
+        })
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .expect("there is at least the identity permutation")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_PLAYERS: [usize; 3] = [0, 1, 2];
+
+    #[test]
+    fn test_export_dot_starts_with_all_matches() {
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+
+        let graph = explore(&playthrough, &DotOptions::default());
+
+        assert_eq!(graph.states[0].to_string(), "start");
+        assert_eq!(
+            graph.edges.keys().filter(|(from, _)| *from == 0).count(),
+            6
+        );
+        assert!(graph.truncated.is_empty());
+    }
+
+    #[test]
+    fn test_export_dot_truncates_the_successors() {
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+        let options = DotOptions {
+            max_states: 1,
+            ..DotOptions::default()
+        };
+
+        let graph = explore(&playthrough, &options);
+
+        // the start is expanded, its six successors aren't
+        assert_eq!(graph.states.len(), 7);
+        assert_eq!(graph.truncated, (1..7).collect());
+        assert!(graph.dead_ends.is_empty());
+        let dot = export_dot(&playthrough, &options);
+        assert_eq!(dot.matches("style=dashed").count(), 6);
+    }
+
+    #[test]
+    fn test_export_dot_collapse_symmetric() {
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+        let options = DotOptions {
+            collapse_symmetric: true,
+            ..DotOptions::default()
+        };
+
+        let full = explore(&playthrough, &DotOptions::default());
+        let collapsed = explore(&playthrough, &options);
+
+        // all six first matches are the same up to renaming the players
+        assert_eq!(collapsed.edges.keys().filter(|(f, _)| *f == 0).count(), 1);
+        assert_eq!(collapsed.edges[&(0, 1)].len(), 6);
+        assert!(collapsed.states.len() < full.states.len());
+    }
+
+    #[test]
+    fn test_export_dot_highlights_dead_ends() {
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+        playthrough.play_match((2, 1)).unwrap();
+        playthrough.play_match((0, 2)).unwrap();
+        playthrough.play_match((1, 0)).unwrap();
+
+        let dot = export_dot(&playthrough, &DotOptions::default());
+
+        assert!(dot.starts_with("digraph tttable {"));
+        assert!(
+            dot.contains("s0 [label=\"0-2 1-0 / 0-2 1-0 2-1\", style=filled")
+        );
+        assert!(!dot.contains("->"));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::symmetry::{self, Symmetry};
+use crate::tt::{TtMatch, TtPlaythrough};
+
+#[derive(Debug, Clone)]
+pub struct EnumerateOptions {
+    /// number of matches to append to the playthrough's history
+    pub length: usize,
+    /// only search schedules which are canonical up to player relabeling
+    pub reduce_symmetry: bool,
+    /// also treat schedules with swapped sides as the same
+    pub mirror: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enumeration {
+    /// the found schedules, only one per class if symmetry is reduced
+    pub sequences: Vec<Vec<TtMatch>>,
+    /// number of schedules without any reduction
+    pub total: usize,
+    /// number of distinct schedules up to symmetry
+    pub distinct: usize,
+}
+
+/// Finds every sequence of `options.length` matches the rules allow after the
+/// playthrough's current history.
+///
+/// With `reduce_symmetry` only canonical prefixes are expanded, the total
+/// count is then recovered from the size of each schedule's class.
+pub fn enumerate(
+    playthrough: &TtPlaythrough,
+    options: &EnumerateOptions,
+) -> Enumeration {
+    let history = playthrough.matches();
+    let symmetries = symmetry::stabilizing(
+        symmetry::respecting_rules(
+            symmetry::symmetries(playthrough.players(), options.mirror),
+            playthrough,
+        ),
+        history,
+    );
+
+    let mut sequences = vec![];
+    search(
+        &mut playthrough.clone(),
+        &mut vec![],
+        options,
+        options.reduce_symmetry.then_some(symmetries.as_slice()),
+        &mut sequences,
+    );
+
+    if options.reduce_symmetry {
+        Enumeration {
+            total: sequences
+                .iter()
+                .map(|s| symmetry::orbit_size(s, &symmetries))
+                .sum(),
+            distinct: sequences.len(),
+            sequences,
+        }
+    } else {
+        Enumeration {
+            total: sequences.len(),
+            distinct: symmetry::deduplicate(&sequences, &symmetries).len(),
+            sequences,
+        }
+    }
+}
+
+fn search(
+    playthrough: &mut TtPlaythrough,
+    prefix: &mut Vec<TtMatch>,
+    options: &EnumerateOptions,
+    symmetries: Option<&[Symmetry]>,
+    found: &mut Vec<Vec<TtMatch>>,
+) {
+    if prefix.len() == options.length {
+        found.push(prefix.clone());
+        return;
+    }
+
+    for players in playthrough.candidate_matches() {
+        if !playthrough.check_match_possible(players) {
+            continue;
+        }
+
+        prefix.push(TtMatch::new(players.0, players.1));
+
+        // a canonical schedule only has canonical prefixes
+        if symmetries.is_none_or(|s| symmetry::is_canonical(prefix, s)) {
+            let mut next = playthrough.clone();
+            next.play_match(players).expect("checked above");
+            search(&mut next, prefix, options, symmetries, found);
+        }
+
+        prefix.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::Rule;
+
+    const TEST_PLAYERS: [usize; 3] = [0, 1, 2];
+
+    fn options(length: usize, reduce_symmetry: bool) -> EnumerateOptions {
+        EnumerateOptions {
+            length,
+            reduce_symmetry,
+            mirror: false,
+        }
+    }
+
+    #[test]
+    fn test_enumerate_first_match() {
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+
+        let all = enumerate(&playthrough, &options(1, false));
+        assert_eq!(all.total, 6);
+        assert_eq!(all.distinct, 1);
+        assert_eq!(all.sequences.len(), 6);
+
+        let reduced = enumerate(&playthrough, &options(1, true));
+        assert_eq!(reduced.total, 6);
+        assert_eq!(reduced.distinct, 1);
+        assert_eq!(reduced.sequences, vec![vec![TtMatch::new(0, 1)]]);
+    }
+
+    #[test]
+    fn test_enumerate_reduced_counts_match_full_search() {
+        for (players, rule_4) in [
+            (TEST_PLAYERS.to_vec(), false),
+            (TEST_PLAYERS.to_vec(), true),
+            (vec![0, 1, 2, 3], false),
+            (vec![0, 1, 2, 3], true),
+        ] {
+            let mut playthrough = TtPlaythrough::new(players, 2).unwrap();
+            if !rule_4 {
+                playthrough =
+                    playthrough.without_rule(Rule::SameSidesVsSameOpponent);
+            }
+
+            for mirror in [true, false] {
+                let all = enumerate(
+                    &playthrough,
+                    &EnumerateOptions {
+                        mirror,
+                        ..options(5, false)
+                    },
+                );
+                let reduced = enumerate(
+                    &playthrough,
+                    &EnumerateOptions {
+                        mirror,
+                        ..options(5, true)
+                    },
+                );
+
+                assert_eq!(all.total, reduced.total);
+                assert_eq!(all.distinct, reduced.distinct);
+                assert_eq!(reduced.sequences.len(), reduced.distinct);
+            }
+        }
+    }
+
+    #[test]
+    fn test_enumerate_after_history() {
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+
+        // 1-2 and 2-0 are both possible and can't be relabeled into each
+        // other without changing the 0-1 before
+        let reduced = enumerate(&playthrough, &options(1, true));
+        assert_eq!(reduced.total, 2);
+        assert_eq!(reduced.distinct, 2);
+    }
+}
@@ -0,0 +1,130 @@
+use std::cmp::Reverse;
+
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::recommend::RecommendWeights;
+use crate::tt::TtPlaythrough;
+
+/// Picks the top match of `TtPlaythrough::recommend_next_matches`.
+pub fn next_match_fairest(
+    playthrough: &TtPlaythrough,
+    weights: &RecommendWeights,
+) -> Option<(usize, usize)> {
+    playthrough
+        .recommend_next_matches(weights)
+        .first()
+        .map(|recommendation| recommendation.players)
+}
+
+/// Picks one of the matches the rules allow next, preferring the players who
+/// sat out the longest and so are closest to the rule 5 limit. Matches which
+/// break soft rules are only picked if there is no match without penalty,
+/// the lowest penalty first. Ties are broken randomly.
+pub fn next_match_most_rested<R: Rng>(
+    playthrough: &TtPlaythrough,
+    rng: &mut R,
+) -> Option<(usize, usize)> {
+    let rests = playthrough.rests();
+
+    // the lowest penalty first, then the longer rest of the two, then the
+    // rest of both together
+    let priority = |players: &(usize, usize)| {
+        let (a, b) = (rests[&players.0], rests[&players.1]);
+        let penalty = playthrough.match_penalty(*players);
+        (Reverse(penalty), a.max(b), a + b)
+    };
+
+    let possible = playthrough
+        .candidate_matches()
+        .into_iter()
+        .filter(|players| playthrough.check_match_possible(*players))
+        .collect_vec();
+
+    let best = possible.iter().map(priority).max()?;
+
+    possible
+        .into_iter()
+        .filter(|players| priority(players) == best)
+        .collect_vec()
+        .choose(rng)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::{Constraint, Rule, TtMatch};
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const TEST_PLAYERS: [usize; 5] = [0, 1, 2, 3, 4];
+
+    #[test]
+    fn test_next_match_most_rested() {
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 3)).unwrap();
+
+        // 4 didn't play at all, 0 and 1 sat out one match
+        let next = next_match_most_rested(&playthrough, &mut rng)
+            .map(|players| TtMatch::new(players.0, players.1))
+            .unwrap();
+        assert!(next.has_player(4));
+        assert!(next.has_player(0) || next.has_player(1));
+    }
+
+    #[test]
+    fn test_next_match_lowest_penalty() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2)
+            .unwrap()
+            .with_constraint(Rule::SameSide, Constraint::Soft(3))
+            .with_constraint(
+                Rule::SameSidesVsSameOpponent,
+                Constraint::Soft(1),
+            );
+        let mut rng = StdRng::seed_from_u64(0);
+
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((1, 2)).unwrap();
+        playthrough.play_match((2, 0)).unwrap();
+
+        // no match keeps all rules anymore, 0-1 only breaks rule 4 and 1-0
+        // breaks rule 3, everything else breaks the hard rule 2
+        assert_eq!(playthrough.match_penalty((0, 1)), 1);
+        assert_eq!(playthrough.match_penalty((1, 0)), 3);
+
+        let next = next_match_most_rested(&playthrough, &mut rng);
+        assert_eq!(next, Some((0, 1)));
+    }
+
+    #[test]
+    fn test_play_most_rested_keeps_max_rest() {
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent)
+            .with_max_rest(2)
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..200 {
+            let next = next_match_most_rested(&playthrough, &mut rng);
+            playthrough.play_match(next.unwrap()).unwrap();
+        }
+
+        for player in TEST_PLAYERS {
+            let longest_rest = playthrough
+                .matches()
+                .split(|m| m.has_player(player))
+                .map(|rest| rest.len())
+                .max();
+            assert!(longest_rest <= Some(2));
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::timed::Date;
+use crate::tt::{TtError, TtMatch, TtPlaythrough};
+
+/// Version of the history file format, see `tt::SCHEMA_VERSION` for the
+/// policy.
+pub const HISTORY_VERSION: u32 = 1;
+
+/// One evening at the table: who played, numbered as in its playthrough,
+/// and the matches in order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub date: Date,
+    /// names in player order, the first is player 0
+    pub names: Vec<String>,
+    pub matches: Vec<TtMatch>,
+    /// the winner of every match, empty if no results were kept
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub winners: Vec<Option<usize>>,
+}
+
+impl SessionRecord {
+    /// `names` has to name every player of the playthrough
+    pub fn new(
+        date: Date,
+        names: Vec<String>,
+        playthrough: &TtPlaythrough,
+    ) -> Result<Self, HistoryError> {
+        if let Some(player) =
+            playthrough.players().iter().find(|p| **p >= names.len())
+        {
+            return Err(HistoryError::Invalid(format!(
+                "player {player} has no name"
+            )));
+        }
+
+        Ok(Self {
+            date,
+            names,
+            matches: playthrough.matches().to_vec(),
+            winners: vec![],
+        })
+    }
+
+    /// `winners` has one entry per match, `None` where the result is unknown
+    pub fn with_winners(
+        mut self,
+        winners: Vec<Option<usize>>,
+    ) -> Result<Self, HistoryError> {
+        self.check_winners(&winners)?;
+
+        self.winners = winners;
+        Ok(self)
+    }
+
+    fn check_winners(
+        &self,
+        winners: &[Option<usize>],
+    ) -> Result<(), HistoryError> {
+        let takes_part = |(m, winner): (&TtMatch, &Option<usize>)| {
+            winner.is_none_or(|w| w == m.left() || w == m.right())
+        };
+
+        if winners.len() != self.matches.len()
+            || !self.matches.iter().zip(winners.iter()).all(takes_part)
+        {
+            return Err(HistoryError::Invalid(
+                "every match needs a winner who played it, or none".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// what `new` and `with_winners` make sure of, for sessions read from a
+    /// storage: every player has a name, nobody plays against themselves and
+    /// the winners played their matches
+    pub(crate) fn check(&self) -> Result<(), HistoryError> {
+        let invalid = |message: String| {
+            Err(HistoryError::Invalid(format!(
+                "invalid session of {}: {message}",
+                self.date
+            )))
+        };
+
+        for m in self.matches.iter() {
+            if m.left() == m.right() {
+                return invalid(format!("player {} plays alone", m.left()));
+            }
+            for player in [m.left(), m.right()] {
+                if player >= self.names.len() {
+                    return invalid(format!("player {player} has no name"));
+                }
+            }
+        }
+        if !self.winners.is_empty() {
+            self.check_winners(&self.winners)
+                .or_else(|error| invalid(error.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// the session's matches played again in `playthrough`, which starts
+    /// with the same players
+    pub fn replay(
+        &self,
+        playthrough: TtPlaythrough,
+    ) -> Result<TtPlaythrough, TtError> {
+        let mut playthrough = playthrough;
+
+        for m in self.matches.iter() {
+            playthrough.force_match((m.left(), m.right()))?;
+        }
+
+        Ok(playthrough)
+    }
+
+    /// (left, right) games of every player by name
+    pub fn side_counts(&self) -> BTreeMap<String, (usize, usize)> {
+        let mut counts = BTreeMap::new();
+
+        for m in self.matches.iter() {
+            let name = |player: usize| self.names[player].clone();
+            counts.entry(name(m.left())).or_insert((0, 0)).0 += 1;
+            counts.entry(name(m.right())).or_insert((0, 0)).1 += 1;
+        }
+
+        counts
+    }
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    /// an error of a database storage, e.g. `sqlite::SqliteHistory`
+    Database(Box<dyn std::error::Error + Send + Sync>),
+    Invalid(String),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HistoryError::Io(error) => {
+                write!(f, "can't access the history: {error}")
+            }
+            HistoryError::Parse(error) => {
+                write!(f, "invalid history: {error}")
+            }
+            HistoryError::Database(error) => {
+                write!(f, "history database error: {error}")
+            }
+            HistoryError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+/// Where the sessions of every evening are kept.
+pub trait Storage {
+    /// the sessions from `from` to `to`, both included and open if `None`,
+    /// oldest first
+    fn sessions(
+        &self,
+        from: Option<Date>,
+        to: Option<Date>,
+    ) -> Result<Vec<SessionRecord>, HistoryError>;
+
+    fn add_session(
+        &mut self,
+        session: SessionRecord,
+    ) -> Result<(), HistoryError>;
+
+    /// (left, right) games of every player by name over the sessions from
+    /// `from` to `to`
+    fn side_totals(
+        &self,
+        from: Option<Date>,
+        to: Option<Date>,
+    ) -> Result<BTreeMap<String, (usize, usize)>, HistoryError> {
+        let mut totals: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+
+        for session in self.sessions(from, to)? {
+            for (name, (left, right)) in session.side_counts() {
+                let total = totals.entry(name).or_default();
+                total.0 += left;
+                total.1 += right;
+            }
+        }
+
+        Ok(totals)
+    }
+}
+
+/// The history as a single JSON file, rewritten for every new session.
+#[derive(Debug)]
+pub struct JsonHistory {
+    path: PathBuf,
+    sessions: Vec<SessionRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    sessions: Vec<SessionRecord>,
+}
+
+impl JsonHistory {
+    /// an empty history if the file doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, HistoryError> {
+        let path = path.into();
+
+        let sessions = match fs::read_to_string(&path) {
+            Ok(json) => {
+                let file: HistoryFile =
+                    serde_json::from_str(&json).map_err(HistoryError::Parse)?;
+                if file.version > HISTORY_VERSION {
+                    return Err(HistoryError::Invalid(format!(
+                        "history version {} is newer than {HISTORY_VERSION}",
+                        file.version
+                    )));
+                }
+                for session in file.sessions.iter() {
+                    session.check()?;
+                }
+                file.sessions
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => vec![],
+            Err(error) => return Err(HistoryError::Io(error)),
+        };
+
+        Ok(Self { path, sessions })
+    }
+
+    fn save(&self) -> Result<(), HistoryError> {
+        let file = HistoryFile {
+            version: HISTORY_VERSION,
+            sessions: self.sessions.clone(),
+        };
+        let json =
+            serde_json::to_string_pretty(&file).map_err(HistoryError::Parse)?;
+        let temporary = self.path.with_extension("tmp");
+
+        fs::write(&temporary, json).map_err(HistoryError::Io)?;
+        fs::rename(&temporary, &self.path).map_err(HistoryError::Io)
+    }
+}
+
+impl Storage for JsonHistory {
+    fn sessions(
+        &self,
+        from: Option<Date>,
+        to: Option<Date>,
+    ) -> Result<Vec<SessionRecord>, HistoryError> {
+        Ok(self
+            .sessions
+            .iter()
+            .filter(|s| from.is_none_or(|from| s.date >= from))
+            .filter(|s| to.is_none_or(|to| s.date <= to))
+            .cloned()
+            .collect())
+    }
+
+    /// kept in date order, after the sessions of the same day
+    fn add_session(
+        &mut self,
+        session: SessionRecord,
+    ) -> Result<(), HistoryError> {
+        let index = self.sessions.partition_point(|s| s.date <= session.date);
+        self.sessions.insert(index, session);
+
+        self.save()
+    }
+}
+
+/// Carries the side totals of earlier sessions over to the players of the
+/// playthrough with the same names, so that e.g. somebody who played mostly
+/// on the worse side last week gets the better side more often now. Fails if
+/// a name with earlier games has no player in the playthrough.
+pub fn carry_over(
+    playthrough: TtPlaythrough,
+    names: &[String],
+    totals: &BTreeMap<String, (usize, usize)>,
+) -> Result<TtPlaythrough, TtError> {
+    let mut playthrough = playthrough;
+
+    for (player, name) in names.iter().enumerate() {
+        if let Some(sides) = totals.get(name) {
+            playthrough = playthrough.with_carried_sides(player, *sides)?;
+        }
+    }
+
+    Ok(playthrough)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn date(text: &str) -> Date {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_json_history_side_totals() {
+        let path = std::env::temp_dir()
+            .join(format!("tttable-history-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 0)).unwrap();
+
+        let mut history = JsonHistory::open(&path).unwrap();
+        for day in ["2024-03-05", "2024-03-04"] {
+            let session = SessionRecord::new(
+                date(day),
+                names(&["Alice", "Bob", "Carol"]),
+                &playthrough,
+            )
+            .unwrap();
+            history.add_session(session).unwrap();
+        }
+
+        // a different numbering on another day
+        let session = SessionRecord::new(
+            date("2024-03-11"),
+            names(&["Bob", "Alice"]),
+            &{
+                let mut p = TtPlaythrough::new(vec![0, 1], 2).unwrap();
+                p.play_match((0, 1)).unwrap();
+                p
+            },
+        )
+        .unwrap();
+        history.add_session(session).unwrap();
+
+        let history = JsonHistory::open(&path).unwrap();
+        let dates = history
+            .sessions(None, None)
+            .unwrap()
+            .iter()
+            .map(|s| s.date.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(dates, ["2024-03-04", "2024-03-05", "2024-03-11"]);
+
+        let totals = history.side_totals(None, None).unwrap();
+        assert_eq!(totals["Alice"], (2, 3));
+        assert_eq!(totals["Bob"], (1, 2));
+
+        let totals = history
+            .side_totals(Some(date("2024-03-05")), Some(date("2024-03-10")))
+            .unwrap();
+        assert_eq!(totals["Alice"], (1, 1));
+        assert_eq!(totals["Carol"], (1, 0));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_json_history_rejects_invalid_sessions() {
+        let path = std::env::temp_dir().join(format!(
+            "tttable-invalid-history-{}.json",
+            std::process::id()
+        ));
+        let session = |matches: &str| {
+            format!(
+                r#"{{"version": 1, "sessions": [{{"date": "2024-03-04",
+                "names": ["Alice", "Bob"], "matches": [{matches}]}}]}}"#
+            )
+        };
+
+        for (matches, error) in [
+            (
+                r#"{"left": 0, "right": 2}"#,
+                "invalid session of 2024-03-04: player 2 has no name",
+            ),
+            (
+                r#"{"left": 1, "right": 1}"#,
+                "invalid session of 2024-03-04: player 1 plays alone",
+            ),
+        ] {
+            fs::write(&path, session(matches)).unwrap();
+            assert_eq!(
+                JsonHistory::open(&path).unwrap_err().to_string(),
+                error
+            );
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_carry_over() {
+        let playthrough = TtPlaythrough::new(vec![0, 1], 2).unwrap();
+        let totals = BTreeMap::from([("Bob".to_string(), (4, 1))]);
+
+        let playthrough =
+            carry_over(playthrough, &names(&["Alice", "Bob"]), &totals)
+                .unwrap();
+
+        assert_eq!(playthrough.total_side_counts()[&1], (4, 1));
+        assert_eq!(playthrough.side_counts()[&1], (0, 0));
+        assert!(SessionRecord::new(
+            date("2024-01-01"),
+            names(&["A"]),
+            &playthrough
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_winners_and_replay() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 0)).unwrap();
+        let session = SessionRecord::new(
+            date("2024-01-01"),
+            names(&["Alice", "Bob", "Carol"]),
+            &playthrough,
+        )
+        .unwrap();
+
+        assert!(session.clone().with_winners(vec![Some(0)]).is_err());
+        assert!(session.clone().with_winners(vec![Some(2), None]).is_err());
+        let session = session.with_winners(vec![Some(1), None]).unwrap();
+        assert_eq!(session.winners, [Some(1), None]);
+
+        let replayed = session
+            .replay(TtPlaythrough::new(vec![0, 1, 2], 2).unwrap())
+            .unwrap();
+        assert_eq!(replayed.matches(), playthrough.matches());
+        assert!(session
+            .replay(TtPlaythrough::new(vec![0, 1], 2).unwrap())
+            .is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::timed::{Date, TimeOfDay, TimedMatch};
+
+/// Content lines are folded after this many bytes, RFC 5545 section 3.1.
+const LINE_LENGTH: usize = 75;
+
+/// An iCalendar file of a timed schedule with one event per match, e.g.
+/// "Alice (L) vs Bob (R)", so everybody sees their games in their calendar.
+/// Times are local ("floating"), as the schedule itself doesn't know a time
+/// zone.
+#[derive(Debug, Clone)]
+pub struct IcsCalendar {
+    date: Date,
+    table: Option<String>,
+    names: BTreeMap<usize, String>,
+    stamp: SystemTime,
+}
+
+impl IcsCalendar {
+    /// `date` is the day the schedule starts on, the file is stamped with
+    /// the current time
+    pub fn new(date: Date) -> Self {
+        Self {
+            date,
+            table: None,
+            names: BTreeMap::new(),
+            stamp: SystemTime::now(),
+        }
+    }
+
+    /// the events' location, also part of their ids so the schedules of
+    /// different tables on the same evening don't replace each other
+    pub fn with_table(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    /// when the file was created instead of now, e.g. for a reproducible
+    /// output
+    pub fn with_stamp(mut self, stamp: SystemTime) -> Self {
+        self.stamp = stamp;
+        self
+    }
+
+    /// players without a name are shown with their number
+    pub fn with_names(mut self, names: BTreeMap<usize, String>) -> Self {
+        self.names = names;
+        self
+    }
+
+    fn name(&self, player: usize) -> String {
+        self.names
+            .get(&player)
+            .cloned()
+            .unwrap_or_else(|| player.to_string())
+    }
+
+    /// `20240308T180000`, on the next day for times past midnight
+    fn date_time(&self, time: TimeOfDay) -> String {
+        let minutes = time.minutes_after_midnight();
+        let date = self.date.plus_days(minutes / (24 * 60));
+
+        format!(
+            "{}T{:02}{:02}00",
+            date.to_string().replace('-', ""),
+            minutes / 60 % 24,
+            minutes % 60
+        )
+    }
+
+    /// the stamp in UTC, e.g. `20240308T170512Z`
+    fn stamp(&self) -> String {
+        let seconds = self
+            .stamp
+            .duration_since(UNIX_EPOCH)
+            .expect("the stamp is after 1970")
+            .as_secs();
+        let date = Date::from_days_since_epoch((seconds / 86_400) as i64);
+        let seconds = seconds % 86_400;
+
+        format!(
+            "{}T{:02}{:02}{:02}Z",
+            date.to_string().replace('-', ""),
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+
+    /// `-` and the table with letters and digits only, empty without one
+    fn uid_table(&self) -> String {
+        self.table.as_ref().map_or(String::new(), |table| {
+            let table: String = table
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '-' })
+                .collect();
+            format!("-{table}")
+        })
+    }
+
+    pub fn render(&self, matches: &[TimedMatch]) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//tttable//timed schedule//EN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+        ];
+
+        let stamp = self.stamp();
+        let table = self.uid_table();
+
+        for (number, m) in matches.iter().enumerate() {
+            let start = self.date_time(m.start);
+            let summary = format!(
+                "{} (L) vs {} (R)",
+                self.name(m.tt_match.left()),
+                self.name(m.tt_match.right())
+            );
+
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{start}-{}{table}@tttable", number + 1));
+            lines.push(format!("DTSTAMP:{stamp}"));
+            lines.push(format!("DTSTART:{start}"));
+            lines.push(format!("DTEND:{}", self.date_time(m.end)));
+            lines.push(format!("SUMMARY:{}", escape(&summary)));
+            if let Some(table) = &self.table {
+                lines.push(format!("LOCATION:{}", escape(table)));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+
+        lines.push("END:VCALENDAR".to_string());
+
+        lines.iter().fold(String::new(), |mut ics, line| {
+            write!(ics, "{}\r\n", fold(line)).expect("strings are writable");
+            ics
+        })
+    }
+}
+
+/// a TEXT value with its special characters escaped
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// a content line split into lines of at most `LINE_LENGTH` bytes, the
+/// continuations starting with a space, never within a character
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for character in line.chars() {
+        if length + character.len_utf8() > LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(character);
+        length += character.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::TtMatch;
+
+    fn timed_match(
+        start: &str,
+        end: &str,
+        players: (usize, usize),
+    ) -> TimedMatch {
+        TimedMatch {
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+            tt_match: TtMatch::new(players.0, players.1),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let start: TimeOfDay = "23:45".parse().unwrap();
+        let matches = [
+            timed_match("23:30", "23:45", (0, 1)),
+            TimedMatch {
+                start,
+                end: start.plus(15),
+                tt_match: TtMatch::new(2, 0),
+            },
+        ];
+        let names = BTreeMap::from([
+            (0, "Alice".to_string()),
+            (1, "Bob, Jr.".to_string()),
+        ]);
+
+        let stamp = UNIX_EPOCH + std::time::Duration::from_secs(1_735_650_000);
+        let ics = IcsCalendar::new("2024-12-31".parse().unwrap())
+            .with_table("Table 1")
+            .with_names(names)
+            .with_stamp(stamp)
+            .render(&matches);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("SUMMARY:Alice (L) vs Bob\\, Jr. (R)\r\n"));
+        assert!(ics.contains("SUMMARY:2 (L) vs Alice (R)\r\n"));
+        assert!(ics.contains("LOCATION:Table 1\r\n"));
+        assert!(ics.contains("UID:20241231T233000-1-Table-1@tttable\r\n"));
+        assert_eq!(ics.matches("DTSTAMP:20241231T130000Z\r\n").count(), 2);
+        assert!(
+            ics.contains("DTSTART:20241231T233000\r\nDTEND:20241231T234500")
+        );
+        // the second match ends on the next day
+        assert!(
+            ics.contains("DTSTART:20241231T234500\r\nDTEND:20250101T000000")
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        let line = format!("SUMMARY:{}", "ä".repeat(40));
+        let folded = fold(&line);
+
+        assert!(folded.split("\r\n").all(|part| part.len() <= LINE_LENGTH));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+        assert_eq!(fold("short"), "short");
+    }
+}
@@ -0,0 +1,48 @@
+//! Fair sequences of matches for a game with two players on two different
+//! sides, e.g. ping pong on a table which isn't the same on both ends.
+//!
+//! A `TtPlaythrough` keeps the matches played so far and checks new ones
+//! against its rules, the other modules generate, recommend or render
+//! schedules on top of it.
+//!
+//! Features: `serde`, `config` (TOML session files), `render` (reports,
+//! score sheets and iCalendar files), `history` (earlier sessions and their
+//! side totals), `sqlite` (the history in a database), `cli` (the `tttable`
+//! binary) and `server` (the `tttable-server` binary and its JSON API), the
+//! last two on by default, and `wasm` (a JavaScript API, see
+//! `wasm::Scheduler`).
+
+pub mod bracket;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod dot;
+pub mod enumerate;
+pub mod generate;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "render")]
+pub mod ics;
+pub mod plan;
+pub mod recommend;
+#[cfg(feature = "render")]
+pub mod report;
+pub mod round_robin;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "render")]
+pub mod sheet;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod swiss;
+mod symmetry;
+pub mod timed;
+pub mod tt;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod winner_stays;
+
+pub use recommend::{RecommendWeights, Recommendation};
+pub use tt::{
+    Constraint, GamesLimit, Rule, RuleSet, SideDisadvantage, TtError, TtMatch,
+    TtPlaythrough, TtState,
+};
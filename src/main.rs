@@ -1,9 +1,31 @@
 use rand::seq::SliceRandom;
+use std::collections::BTreeMap;
+use std::env;
+use std::io::Write;
+use std::process;
 use std::time::Instant;
 
-use tt::TtPlaythrough;
-
-mod tt;
+use itertools::Itertools;
+use tttable::bracket::{Bracket, BracketKind};
+use tttable::config::{Config, OutputFormat, Strategy};
+use tttable::dot::{self, DotOptions};
+use tttable::enumerate::{self, EnumerateOptions};
+use tttable::generate;
+use tttable::history::{self, Date, JsonHistory, SessionRecord, Storage};
+use tttable::ics::IcsCalendar;
+use tttable::plan::LookaheadPlanner;
+use tttable::report::{self, ReportFormat, ReportOptions};
+use tttable::round_robin::{self, RoundRobinKind};
+use tttable::sheet::{ScoreSheet, SheetFormat};
+#[cfg(feature = "sqlite")]
+use tttable::sqlite::SqliteHistory;
+use tttable::swiss::Swiss;
+use tttable::timed::{Availability, TimeOfDay, TimedSchedule};
+use tttable::winner_stays::WinnerStays;
+use tttable::{
+    Constraint, GamesLimit, RecommendWeights, Rule, SideDisadvantage,
+    TtPlaythrough,
+};
 
 const GAMES_TOTAL: usize = 10usize.pow(5);
 const PLAYERS: [usize; 3] = [0, 1, 2];
@@ -13,7 +35,795 @@ const MATCHES: [(usize, usize); 6] =
 fn main() {
     env_logger::init();
 
-    let mut playthrough = TtPlaythrough::new(PLAYERS.into(), 2);
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("--config") => run_config(
+            args.get(1)
+                .unwrap_or_else(|| exit_with("usage: tttable --config FILE")),
+        ),
+        Some("bracket") => print_bracket(&args[1..]),
+        Some("dot") => print_dot(&args[1..]),
+        Some("enumerate") => print_enumeration(&args[1..]),
+        Some("generate") => print_generated(&args[1..]),
+        Some("recommend") => print_recommendations(&args[1..]),
+        Some("sides") => print_side_totals(&args[1..]),
+        Some("round-robin") => print_round_robin(&args[1..]),
+        Some("swiss") => print_swiss(&args[1..]),
+        Some("timed") => print_timed(&args[1..]),
+        Some("winner-stays") => print_winner_stays(&args[1..]),
+        _ => run_random_games(),
+    }
+}
+
+/// playthrough of `PLAYERS` or `--players N` players, with the rule 2
+/// limits given as `--limit [PLAYER:]MAX_GAMES[/WINDOW]`, e.g. `--limit 0:1`
+/// allows player 0 only one game in a row, `--limit 3/5` everyone 3 games in
+/// any 5 matches, rule 4 dropped by `--without-rule-4`, rule 5 enabled by
+/// `--max-rest N`, rules made soft by `--soft RULE:WEIGHT`, e.g.
+/// `--soft 3:10`, and sides of unequal quality given by
+/// `--disadvantage [AFTER_MATCHES:]LEFT/RIGHT`, e.g. `--disadvantage 0/1
+/// --disadvantage 12:0/4` for a slightly worse right side which gets sun
+/// glare after 12 matches
+fn new_playthrough(args: &[String]) -> TtPlaythrough {
+    let flag_value = |flag: &str| flag_value(args, flag);
+
+    let players = match flag_value("--players") {
+        Some(count) => (0..count).collect(),
+        None => PLAYERS.into(),
+    };
+    let mut playthrough =
+        TtPlaythrough::new(players, 2).unwrap_or_else(|error| exit_with(error));
+
+    if args.iter().any(|arg| arg == "--without-rule-4") {
+        playthrough = playthrough.without_rule(Rule::SameSidesVsSameOpponent);
+    }
+
+    if let Some(max_rest) = flag_value("--max-rest") {
+        playthrough = playthrough
+            .with_max_rest(max_rest)
+            .unwrap_or_else(|error| exit_with(error));
+    }
+
+    for (flag, value) in args.iter().tuple_windows() {
+        if flag != "--soft" {
+            continue;
+        }
+
+        let (rule, weight) = value
+            .split_once(':')
+            .and_then(|(rule, weight)| {
+                let rule = Rule::from_number(rule.parse().ok()?)?;
+                Some((rule, weight.parse().ok()?))
+            })
+            .unwrap_or_else(|| exit_with(format!("invalid --soft {value}")));
+
+        playthrough =
+            playthrough.with_constraint(rule, Constraint::Soft(weight));
+    }
+
+    for (flag, value) in args.iter().tuple_windows() {
+        if flag != "--limit" {
+            continue;
+        }
+
+        let (player, limit) = match value.split_once(':') {
+            Some((player, limit)) => (Some(player), limit),
+            None => (None, value.as_str()),
+        };
+        let parse = |number: &str| -> usize {
+            number.parse().unwrap_or_else(|_| {
+                exit_with(format!("invalid --limit {value}"))
+            })
+        };
+        let limit = match limit.split_once('/') {
+            Some((max_games, window)) => {
+                GamesLimit::within(parse(max_games), parse(window))
+            }
+            None => GamesLimit::consecutive(parse(limit)),
+        };
+
+        playthrough = match player {
+            Some(player) => playthrough.with_games_limit(parse(player), limit),
+            None => playthrough.with_default_games_limit(limit),
+        }
+        .unwrap_or_else(|error| exit_with(error));
+    }
+
+    let mut side_disadvantage: Option<SideDisadvantage> = None;
+    for (flag, value) in args.iter().tuple_windows() {
+        if flag != "--disadvantage" {
+            continue;
+        }
+
+        let (played, sides) = match value.split_once(':') {
+            Some((played, sides)) => (played, sides),
+            None => ("0", value.as_str()),
+        };
+        let (played, left, right) = sides
+            .split_once('/')
+            .and_then(|(left, right)| {
+                Some((
+                    played.parse().ok()?,
+                    left.parse().ok()?,
+                    right.parse().ok()?,
+                ))
+            })
+            .unwrap_or_else(|| {
+                exit_with(format!("invalid --disadvantage {value}"))
+            });
+
+        side_disadvantage = Some(match side_disadvantage {
+            Some(d) => d.after_matches(played, left, right),
+            None => {
+                SideDisadvantage::new(0, 0).after_matches(played, left, right)
+            }
+        });
+    }
+    if let Some(side_disadvantage) = side_disadvantage {
+        playthrough = playthrough.with_side_disadvantage(side_disadvantage);
+    }
+
+    playthrough
+}
+
+/// the number following `flag`, e.g. `--players 5`
+fn flag_value(args: &[String], flag: &str) -> Option<usize> {
+    let (_, value) = args.iter().tuple_windows().find(|(f, _)| *f == flag)?;
+    let number = value
+        .parse()
+        .unwrap_or_else(|_| exit_with(format!("invalid {flag} {value}")));
+
+    Some(number)
+}
+
+/// the text following `flag`, e.g. `--names Alice,Bob`
+fn flag_text<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let (_, value) = args.iter().tuple_windows().find(|(f, _)| *f == flag)?;
+
+    Some(value.as_str())
+}
+
+/// the date following `flag`, e.g. `--since 2024-03-01`
+fn flag_date(args: &[String], flag: &str) -> Option<Date> {
+    let value = flag_text(args, flag)?;
+
+    Some(
+        value.parse().unwrap_or_else(|error| {
+            exit_with(format!("invalid {flag}: {error}"))
+        }),
+    )
+}
+
+/// `--report md` or `--report html`
+fn report_format(args: &[String]) -> Option<ReportFormat> {
+    let (_, name) = args
+        .iter()
+        .tuple_windows()
+        .find(|(f, _)| *f == "--report")?;
+
+    let format = ReportFormat::from_name(name).unwrap_or_else(|| {
+        exit_with(format!("invalid --report {name}, use md or html"))
+    });
+
+    Some(format)
+}
+
+/// how a schedule is printed
+enum Output {
+    Log,
+    Report(ReportFormat),
+    Sheet(SheetFormat),
+}
+
+/// the matches as a score sheet in the `--sheet svg` or `--sheet pdf`
+/// format with `--names NAME0,NAME1,...`, as a report in the `--report`
+/// format, else as log lines
+fn print_schedule(args: &[String], playthrough: &TtPlaythrough) {
+    let flag_text = |flag: &str| flag_text(args, flag);
+
+    let output = match (flag_text("--sheet"), report_format(args)) {
+        (Some(name), _) => {
+            Output::Sheet(SheetFormat::from_name(name).unwrap_or_else(|| {
+                exit_with(format!("invalid --sheet {name}, use svg or pdf"))
+            }))
+        }
+        (None, Some(format)) => Output::Report(format),
+        (None, None) => Output::Log,
+    };
+
+    let names: BTreeMap<usize, String> = flag_text("--names")
+        .map(|names| names.split(',').map(String::from).enumerate().collect())
+        .unwrap_or_default();
+
+    output_schedule(playthrough, output, "Table tennis", names, None);
+}
+
+fn output_schedule(
+    playthrough: &TtPlaythrough,
+    output: Output,
+    title: &str,
+    names: BTreeMap<usize, String>,
+    config: Option<String>,
+) {
+    match output {
+        Output::Log => {
+            playthrough.log_matches_so_far();
+            if let Some(config) = config {
+                println!("\nCONFIG:\n{config}");
+            }
+        }
+        Output::Report(format) => {
+            let options = ReportOptions { names, config };
+            print!("{}", report::render(playthrough, format, &options));
+        }
+        Output::Sheet(format) => {
+            let mut sheet = ScoreSheet::new(title).with_names(names);
+            if let Some(config) = config {
+                sheet = sheet.with_config(config);
+            }
+
+            std::io::stdout()
+                .write_all(&sheet.render(playthrough.matches(), format))
+                .expect("stdout is writable");
+        }
+    }
+}
+
+/// usage: `tttable --config FILE`, generates and prints a schedule as
+/// described in the TOML file, see `config::Config`
+fn run_config(path: &str) {
+    let config = Config::load(path).unwrap_or_else(|error| exit_with(error));
+
+    let mut playthrough = config.playthrough();
+
+    let round_robin_kind = match config.generator.strategy {
+        Strategy::RoundRobin => Some(RoundRobinKind::Single),
+        Strategy::DoubleRoundRobin => Some(RoundRobinKind::Double),
+        _ => None,
+    };
+
+    match round_robin_kind {
+        Some(kind) => {
+            let round_robin = round_robin::round_robin(&playthrough, kind);
+            for violation in round_robin.violations.iter() {
+                eprintln!(
+                    "Match {} {} breaks {}",
+                    violation.index + 1,
+                    violation.tt_match,
+                    violation.rules.iter().join(", ")
+                );
+            }
+            playthrough = round_robin.playthrough;
+        }
+        None => play_generated(
+            &mut playthrough,
+            config.generator.matches,
+            config.generator.strategy,
+            config.generator.lookahead,
+            &config.weights(),
+        ),
+    }
+
+    let output = match config.output.format {
+        OutputFormat::Log => Output::Log,
+        OutputFormat::Markdown => Output::Report(ReportFormat::Markdown),
+        OutputFormat::Html => Output::Report(ReportFormat::Html),
+        OutputFormat::Svg => Output::Sheet(SheetFormat::Svg),
+        OutputFormat::Pdf => Output::Sheet(SheetFormat::Pdf),
+    };
+
+    output_schedule(
+        &playthrough,
+        output,
+        &config.output.title,
+        config.names(),
+        Some(config.to_toml()),
+    );
+}
+
+/// usage: `tttable dot [--without-rule-4] [--collapse-symmetric]
+/// [--no-dead-ends]`, pipe the output into `dot -Tsvg`
+fn print_dot(flags: &[String]) {
+    let has_flag = |flag: &str| flags.iter().any(|f| f == flag);
+
+    let options = DotOptions {
+        collapse_symmetric: has_flag("--collapse-symmetric"),
+        highlight_dead_ends: !has_flag("--no-dead-ends"),
+        ..DotOptions::default()
+    };
+    let playthrough = new_playthrough(flags);
+
+    print!("{}", dot::export_dot(&playthrough, &options));
+}
+
+/// usage: `tttable enumerate <length> [--without-rule-4] [--mirror] [--all]`,
+/// `--all` lists every schedule instead of one per symmetry class
+fn print_enumeration(args: &[String]) {
+    let has_flag = |flag: &str| args.iter().any(|f| f == flag);
+
+    let length = args
+        .first()
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_else(|| {
+            exit_with("usage: tttable enumerate <length> [flags]")
+        });
+
+    let options = EnumerateOptions {
+        length,
+        reduce_symmetry: !has_flag("--all"),
+        mirror: has_flag("--mirror"),
+    };
+    let playthrough = new_playthrough(args);
+
+    let enumeration = enumerate::enumerate(&playthrough, &options);
+
+    for sequence in enumeration.sequences.iter() {
+        println!("{}", sequence.iter().join(" "));
+    }
+
+    println!(
+        "\n{} schedules of {} matches, {} distinct schedules up to symmetry",
+        enumeration.total, length, enumeration.distinct
+    );
+}
+
+/// usage: `tttable recommend [LEFT-RIGHT ...] [--without-rule-4]
+/// [--lookahead K]`, ranks the possible next matches after the given matches
+/// by fairness, with `--lookahead` only the ones with K more matches after
+fn print_recommendations(args: &[String]) {
+    let mut playthrough = new_playthrough(args);
+
+    for game in args.iter().take_while(|arg| !arg.starts_with("--")) {
+        let players = game
+            .split_once('-')
+            .and_then(|(l, r)| Some((l.parse().ok()?, r.parse().ok()?)))
+            .unwrap_or_else(|| {
+                exit_with(format!("invalid match {game}, use LEFT-RIGHT"))
+            });
+
+        if let Err(error) = playthrough.play_match(players) {
+            exit_with(error);
+        }
+    }
+
+    let recommendations = match flag_value(args, "--lookahead") {
+        Some(depth) => LookaheadPlanner::new(depth).recommend(&playthrough),
+        None => {
+            playthrough.recommend_next_matches(&RecommendWeights::default())
+        }
+    };
+
+    println!("match    rest  sides  pairing  penalty  total");
+    for r in recommendations {
+        println!(
+            "{:>2} - {:<2} {:>5} {:>6} {:>8} {:>8} {:>6}",
+            r.players.0,
+            r.players.1,
+            r.rest,
+            r.side_balance,
+            r.pairing,
+            r.penalty,
+            r.total
+        );
+    }
+}
+
+/// usage: `tttable generate <count> [--without-rule-4] [--fair]
+/// [--lookahead K] [--report md|html] [--sheet svg|pdf]
+/// [--history FILE --names A,B,... [--since DATE] [--date DATE]]`, plays
+/// `count` matches preferring the players who sat out the longest, or the
+/// top (lookahead) recommendation with `--fair`; with `--history` the side
+/// totals of the earlier sessions, those since `--since` if given, are
+/// carried over by name and the session is added to the history file
+fn print_generated(args: &[String]) {
+    let has_flag = |flag: &str| args.iter().any(|f| f == flag);
+
+    let count = args
+        .first()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or_else(|| {
+            exit_with("usage: tttable generate <count> [flags]")
+        });
+
+    let strategy = if has_flag("--lookahead") {
+        Strategy::Lookahead
+    } else if has_flag("--fair") {
+        Strategy::Fair
+    } else {
+        Strategy::MostRested
+    };
+
+    let mut playthrough = new_playthrough(args);
+    let mut history = flag_text(args, "--history").map(open_history);
+    let names: Vec<String> = flag_text(args, "--names")
+        .map(|names| names.split(',').map(String::from).collect())
+        .unwrap_or_default();
+
+    if let Some(history) = &history {
+        let totals = history
+            .side_totals(flag_date(args, "--since"), None)
+            .unwrap_or_else(|error| exit_with(error));
+        playthrough = history::carry_over(playthrough, &names, &totals)
+            .unwrap_or_else(|error| exit_with(error));
+    }
+
+    play_generated(
+        &mut playthrough,
+        count,
+        strategy,
+        flag_value(args, "--lookahead").unwrap_or(0),
+        &RecommendWeights::default(),
+    );
+
+    if let Some(history) = &mut history {
+        let date = flag_date(args, "--date").unwrap_or_else(Date::today);
+        SessionRecord::new(date, names, &playthrough)
+            .and_then(|session| history.add_session(session))
+            .unwrap_or_else(|error| exit_with(error));
+    }
+
+    print_schedule(args, &playthrough);
+}
+
+/// usage: `tttable sides --history FILE [--from DATE] [--to DATE]`, the
+/// games on the left and the right side of every player in the sessions
+/// of the history file, from and to the given dates if any
+fn print_side_totals(args: &[String]) {
+    let history =
+        open_history(flag_text(args, "--history").unwrap_or_else(|| {
+            exit_with("usage: tttable sides --history FILE [flags]")
+        }));
+
+    let totals = history
+        .side_totals(flag_date(args, "--from"), flag_date(args, "--to"))
+        .unwrap_or_else(|error| exit_with(error));
+
+    for (name, (left, right)) in totals {
+        println!("{name}: {left} left, {right} right");
+    }
+}
+
+/// an SQLite database for `.sqlite` and `.db` files, built with the
+/// `sqlite` feature, else a JSON file
+fn open_history(path: &str) -> Box<dyn Storage> {
+    if path.ends_with(".sqlite") || path.ends_with(".db") {
+        #[cfg(feature = "sqlite")]
+        return Box::new(
+            SqliteHistory::open(path).unwrap_or_else(|error| exit_with(error)),
+        );
+        #[cfg(not(feature = "sqlite"))]
+        exit_with(format!("{path}: built without the sqlite feature"));
+    }
+
+    Box::new(JsonHistory::open(path).unwrap_or_else(|error| exit_with(error)))
+}
+
+fn exit_with(error: impl std::fmt::Display) -> ! {
+    eprintln!("{error}");
+    process::exit(1)
+}
+
+/// usage: `tttable timed --start 18:00 --end 21:00 [--minutes 15]
+/// [--available PLAYER:ARRIVE-LEAVE]... [--names A,B,...]
+/// [--ics [--date DATE] [--table NAME]]`, plans `--minutes` long matches
+/// (15 by default) between the players who are there, e.g.
+/// `--available 2:18:30-19:30`, and prints them with their estimated times,
+/// or as an iCalendar file for the day `--date` (today by default) with
+/// `--ics`
+fn print_timed(args: &[String]) {
+    let flag_time = |flag: &str| -> TimeOfDay {
+        let value = flag_text(args, flag).unwrap_or_else(|| {
+            exit_with(format!("usage: tttable timed {flag} HH:MM"))
+        });
+        value.parse().unwrap_or_else(|error| {
+            exit_with(format!("invalid {flag}: {error}"))
+        })
+    };
+    let (start, end) = (flag_time("--start"), flag_time("--end"));
+    let minutes = flag_value(args, "--minutes").unwrap_or(15) as u32;
+
+    let mut schedule = TimedSchedule::new(new_playthrough(args), minutes)
+        .unwrap_or_else(|error| exit_with(error));
+
+    for (flag, value) in args.iter().tuple_windows() {
+        if flag != "--available" {
+            continue;
+        }
+
+        let availability = value
+            .split_once(':')
+            .and_then(|(player, window)| {
+                let (arrive, leave) = window.split_once('-')?;
+                let availability = Availability {
+                    arrive: arrive.parse().ok()?,
+                    leave: leave.parse().ok()?,
+                };
+                Some((player.parse().ok()?, availability))
+            })
+            .unwrap_or_else(|| {
+                exit_with(format!("invalid --available {value}"))
+            });
+
+        schedule = schedule
+            .with_availability(availability.0, availability.1)
+            .unwrap_or_else(|error| exit_with(error));
+    }
+
+    let names: Vec<&str> = flag_text(args, "--names")
+        .map_or(vec![], |names| names.split(',').collect());
+    let name = |player: usize| {
+        names
+            .get(player)
+            .map_or(player.to_string(), |name| name.to_string())
+    };
+
+    let matches = schedule
+        .plan(start, end)
+        .unwrap_or_else(|error| exit_with(error));
+
+    if args.iter().any(|arg| arg == "--ics") {
+        let date = flag_date(args, "--date").unwrap_or_else(Date::today);
+        let mut calendar = IcsCalendar::new(date).with_names(
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .enumerate()
+                .collect(),
+        );
+        if let Some(table) = flag_text(args, "--table") {
+            calendar = calendar.with_table(table);
+        }
+
+        print!("{}", calendar.render(matches));
+        return;
+    }
+
+    for m in matches {
+        let (left, right) = (m.tt_match.left(), m.tt_match.right());
+        println!("{}-{} {} - {}", m.start, m.end, name(left), name(right));
+    }
+}
+
+/// plays `count` matches chosen by `strategy`, reporting the ones which
+/// break soft rules, until no match is possible anymore
+fn play_generated(
+    playthrough: &mut TtPlaythrough,
+    count: usize,
+    strategy: Strategy,
+    lookahead: usize,
+    weights: &RecommendWeights,
+) {
+    let mut random_generator = rand::thread_rng();
+
+    for number in 1..=count {
+        let next = match strategy {
+            Strategy::Lookahead => {
+                let mut planner = LookaheadPlanner::new(lookahead);
+                planner.weights = *weights;
+                planner.next_match(playthrough)
+            }
+            Strategy::Fair => {
+                generate::next_match_fairest(playthrough, weights)
+            }
+            _ => generate::next_match_most_rested(
+                playthrough,
+                &mut random_generator,
+            ),
+        };
+
+        let Some(players) = next else {
+            eprintln!("No match possible after {} matches.", number - 1);
+            break;
+        };
+
+        let broken_rules = playthrough.violated_rules(players);
+        if !broken_rules.is_empty() {
+            eprintln!(
+                "Match {number} {:?} breaks {}",
+                players,
+                broken_rules.iter().join(", ")
+            );
+        }
+
+        playthrough
+            .play_match(players)
+            .expect("the generators only pick possible matches");
+    }
+}
+
+/// usage: `tttable round-robin [--double] [--without-rule-4]
+/// [--report md|html] [--sheet svg|pdf]`, every pairing plays once, or twice
+/// on both sides with `--double`
+fn print_round_robin(args: &[String]) {
+    let has_flag = |flag: &str| args.iter().any(|f| f == flag);
+
+    let kind = if has_flag("--double") {
+        RoundRobinKind::Double
+    } else {
+        RoundRobinKind::Single
+    };
+    let playthrough = new_playthrough(args);
+
+    let round_robin = round_robin::round_robin(&playthrough, kind);
+
+    if has_flag("--report") || has_flag("--sheet") {
+        print_schedule(args, &round_robin.playthrough);
+        return;
+    }
+
+    for (number, tt_match) in round_robin.matches.iter().enumerate() {
+        println!("{:>3}. {}", number + 1, tt_match);
+    }
+
+    for violation in round_robin.violations.iter() {
+        println!(
+            "Match {} {} breaks {}",
+            violation.index + 1,
+            violation.tt_match,
+            violation.rules.iter().join(", ")
+        );
+    }
+
+    for (player, (left, right)) in round_robin.playthrough.side_counts() {
+        println!("Player {player}: {left} left, {right} right");
+    }
+}
+
+/// usage: `tttable bracket [WINNER ...] [--double] [--seeds S1,S2,...]`,
+/// single or double elimination seeded by player number or `--seeds`, with
+/// the winners given in the order of the ready matches
+fn print_bracket(args: &[String]) {
+    let kind = if args.iter().any(|f| f == "--double") {
+        BracketKind::Double
+    } else {
+        BracketKind::Single
+    };
+    let playthrough = new_playthrough(args);
+
+    let seeds = match args.iter().position(|f| f == "--seeds") {
+        Some(index) => args
+            .get(index + 1)
+            .unwrap_or_else(|| {
+                exit_with("usage: tttable bracket [flags] --seeds S1,S2,...")
+            })
+            .split(',')
+            .map(|seed| {
+                seed.parse().unwrap_or_else(|_| {
+                    exit_with(format!("invalid seed {seed} in --seeds"))
+                })
+            })
+            .collect(),
+        None => playthrough.players().to_vec(),
+    };
+    let mut bracket = Bracket::new(playthrough, seeds, kind)
+        .unwrap_or_else(|error| exit_with(error));
+
+    for winner in args.iter().take_while(|arg| !arg.starts_with("--")) {
+        let winner = winner
+            .parse()
+            .unwrap_or_else(|_| exit_with(format!("invalid winner {winner}")));
+
+        if !bracket.record_result(winner) {
+            println!("Player {winner} has no match to win");
+            break;
+        }
+    }
+
+    let played = bracket
+        .matches()
+        .iter()
+        .filter(|m| m.winner.is_some() && m.tt_match.is_some())
+        .count();
+
+    print!("{bracket}");
+    println!("Played: {played}");
+    println!("Ready: {}", bracket.ready_matches().iter().join(" "));
+}
+
+/// usage: `tttable swiss [WINNER ...] [--rounds R]`, pairs Swiss-system
+/// rounds with the winners given in the order of the matches, up to `R`
+/// rounds (default 3), and prints them with the standings
+fn print_swiss(args: &[String]) {
+    let rounds = flag_value(args, "--rounds").unwrap_or(3);
+    let mut winners =
+        args.iter()
+            .take_while(|arg| !arg.starts_with("--"))
+            .map(|winner| {
+                winner.parse().unwrap_or_else(|_| {
+                    exit_with(format!("invalid winner {winner}"))
+                })
+            });
+    let mut swiss = Swiss::new(new_playthrough(args));
+
+    'rounds: for _ in 0..rounds {
+        // every match of the round before has a winner
+        let Some(round) = swiss.pair_next_round().cloned() else {
+            break;
+        };
+
+        for tt_match in round.matches.iter() {
+            let Some(winner) = winners.next() else {
+                break 'rounds;
+            };
+            if !tt_match.has_player(winner) {
+                println!("Player {winner} doesn't play {tt_match}");
+                return;
+            }
+            swiss.record_result(winner);
+        }
+    }
+
+    for (number, round) in swiss.rounds().iter().enumerate() {
+        println!("Round {}", number + 1);
+        for (tt_match, winner) in round.matches.iter().zip(&round.winners) {
+            match winner {
+                Some(winner) => println!("  {tt_match} won by {winner}"),
+                None => println!("  {tt_match}"),
+            }
+        }
+        if let Some(bye) = round.bye {
+            println!("  bye: {bye}");
+        }
+    }
+
+    println!("player  score  buchholz  left  right");
+    for s in swiss.standings() {
+        println!(
+            "{:>6} {:>6} {:>9} {:>5} {:>6}",
+            s.player, s.score, s.buchholz, s.left, s.right
+        );
+    }
+}
+
+/// usage: `tttable winner-stays [WINNER ...] [--without-rule-4]`, the winner
+/// of every match stays at the table and plays the next in the queue, which
+/// is printed after every result
+fn print_winner_stays(args: &[String]) {
+    let mut winner_stays = WinnerStays::new(new_playthrough(args));
+    let print_next = |winner_stays: &WinnerStays| {
+        println!(
+            "Next: {}, queue: {}",
+            winner_stays.current_match(),
+            winner_stays.queue().iter().join(" ")
+        );
+    };
+
+    print_next(&winner_stays);
+
+    for winner in args.iter().take_while(|arg| !arg.starts_with("--")) {
+        let winner = winner
+            .parse()
+            .unwrap_or_else(|_| exit_with(format!("invalid winner {winner}")));
+
+        let current = winner_stays.current_match().clone();
+        if winner_stays.record_result(winner).is_none() {
+            println!("Player {winner} doesn't play {current}");
+            return;
+        }
+        println!("{current} won by {winner}");
+        print_next(&winner_stays);
+    }
+
+    for violation in winner_stays.violations() {
+        println!(
+            "Match {} {} breaks {}",
+            violation.index + 1,
+            violation.tt_match,
+            violation.rules.iter().join(", ")
+        );
+    }
+
+    for (player, (left, right)) in winner_stays.playthrough().side_counts() {
+        println!("Player {player}: {left} left, {right} right");
+    }
+}
+
+fn run_random_games() {
+    // without applying rule 4 (disallow playing on the same site when facing the same opponent again)
+    let mut playthrough = TtPlaythrough::new(PLAYERS.into(), 2)
+        .expect("PLAYERS are valid")
+        .without_rule(Rule::SameSidesVsSameOpponent);
     let mut random_generator = rand::thread_rng();
 
     let starttime = Instant::now(); // bench
@@ -22,7 +832,8 @@ fn main() {
             .choose(&mut random_generator)
             .expect("MATCHES is not empty");
 
-        playthrough.play_match_if_possible(*game, true)
+        // impossible matches are just skipped
+        let _ = playthrough.play_match(*game);
     }
     let elapsed = starttime.elapsed(); // bench
 
@@ -33,8 +844,8 @@ fn main() {
         elapsed, GAMES_TOTAL
     ); // bench
 
-    // without applying rule 4 (disallow playing on the same site when facing the same opponent again)
     playthrough.clear_match_history();
+    playthrough = playthrough.with_rule(Rule::SameSidesVsSameOpponent);
     println!("\n\nRunning with rule 4 applied\n");
     let starttime = Instant::now(); // bench
     for _ in 0..GAMES_TOTAL {
@@ -42,7 +853,8 @@ fn main() {
             .choose(&mut random_generator)
             .expect("MATCHES is not empty");
 
-        playthrough.play_match_if_possible(*game, false)
+        // impossible matches are just skipped
+        let _ = playthrough.play_match(*game);
     }
     let elapsed = starttime.elapsed(); // bench
 
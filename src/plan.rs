@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::recommend::{RecommendWeights, Recommendation};
+use crate::tt::{TtPlaythrough, TtState};
+
+/// Recommends next matches which don't run into a dead end within the next
+/// `depth` matches, using the same rule checks as the playthrough itself.
+#[derive(Debug, Clone)]
+pub struct LookaheadPlanner {
+    pub depth: usize,
+    pub weights: RecommendWeights,
+}
+
+impl LookaheadPlanner {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            weights: RecommendWeights::default(),
+        }
+    }
+
+    /// The recommendations of `TtPlaythrough::recommend_next_matches` which
+    /// can still be followed by `depth` more matches. If none can, the ones
+    /// with the longest continuation are kept so the evening can go on.
+    pub fn recommend(
+        &self,
+        playthrough: &TtPlaythrough,
+    ) -> Vec<Recommendation> {
+        let mut memo = HashMap::new();
+
+        let with_continuation = playthrough
+            .recommend_next_matches(&self.weights)
+            .into_iter()
+            .map(|recommendation| {
+                let mut next = playthrough.clone();
+                next.play_match(recommendation.players)
+                    .expect("only possible matches are recommended");
+
+                let continuation =
+                    self.continuation_length(&next, self.depth, &mut memo);
+
+                (recommendation, continuation)
+            })
+            .collect_vec();
+
+        let longest = with_continuation.iter().map(|(_, c)| *c).max();
+
+        with_continuation
+            .into_iter()
+            .filter(|(_, continuation)| Some(*continuation) == longest)
+            .map(|(recommendation, _)| recommendation)
+            .collect_vec()
+    }
+
+    pub fn next_match(
+        &self,
+        playthrough: &TtPlaythrough,
+    ) -> Option<(usize, usize)> {
+        self.recommend(playthrough)
+            .first()
+            .map(|recommendation| recommendation.players)
+    }
+
+    /// number of matches, at most `depth`, which can still be played one
+    /// after another
+    pub fn continuation_length(
+        &self,
+        playthrough: &TtPlaythrough,
+        depth: usize,
+        memo: &mut HashMap<(TtState, usize), usize>,
+    ) -> usize {
+        if depth == 0 {
+            return 0;
+        }
+
+        let key = (playthrough.state(), depth);
+        if let Some(length) = memo.get(&key) {
+            return *length;
+        }
+
+        let mut longest = 0;
+        for players in playthrough.candidate_matches() {
+            if !playthrough.check_match_possible(players) {
+                continue;
+            }
+
+            let mut next = playthrough.clone();
+            next.play_match(players).expect("checked above");
+
+            longest = longest
+                .max(1 + self.continuation_length(&next, depth - 1, memo));
+
+            if longest == depth {
+                break;
+            }
+        }
+
+        memo.insert(key, longest);
+
+        longest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::GamesLimit;
+
+    const TEST_PLAYERS: [usize; 3] = [0, 1, 2];
+
+    #[test]
+    fn test_continuation_length() {
+        let planner = LookaheadPlanner::new(5);
+        let mut memo = HashMap::new();
+
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+        assert_eq!(planner.continuation_length(&playthrough, 5, &mut memo), 3);
+
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((1, 2)).unwrap();
+        assert_eq!(planner.continuation_length(&playthrough, 5, &mut memo), 1);
+
+        playthrough.play_match((2, 0)).unwrap();
+        assert_eq!(planner.continuation_length(&playthrough, 5, &mut memo), 0);
+    }
+
+    #[test]
+    fn test_recommend_avoids_dead_ends() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .with_default_games_limit(GamesLimit::within(2, 4))
+            .unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 3)).unwrap();
+        playthrough.play_match((1, 2)).unwrap();
+
+        // the fairest match right now leaves no possible match after it
+        let greedy = playthrough.recommend_next_matches(&Default::default());
+        assert_eq!(greedy[0].players, (3, 0));
+
+        let planner = LookaheadPlanner::new(2);
+        let planned = planner.recommend(&playthrough);
+        assert_eq!(planned[0].players, (0, 3));
+
+        // every recommended match can be followed by 2 more matches
+        for recommendation in planned {
+            let mut next = playthrough.clone();
+            next.play_match(recommendation.players).unwrap();
+
+            let length =
+                planner.continuation_length(&next, 2, &mut HashMap::new());
+            assert_eq!(length, 2);
+        }
+    }
+
+    #[test]
+    fn test_recommend_keeps_longest_when_all_run_into_dead_ends() {
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+
+        // with rule 4 three players only get 3 matches in total
+        let planned = LookaheadPlanner::new(4).recommend(&playthrough);
+
+        assert_eq!(planned.len(), 2);
+    }
+}
@@ -0,0 +1,264 @@
+use std::cmp::Reverse;
+
+use itertools::Itertools;
+
+use crate::tt::TtPlaythrough;
+
+/// How much each fairness criterion counts towards a recommendation's total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecommendWeights {
+    pub rest: i64,
+    pub side_balance: i64,
+    pub pairing: i64,
+}
+
+impl Default for RecommendWeights {
+    fn default() -> Self {
+        Self {
+            rest: 1,
+            side_balance: 1,
+            pairing: 1,
+        }
+    }
+}
+
+/// A possible next match with its score per fairness criterion, higher is
+/// fairer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recommendation {
+    pub players: (usize, usize),
+    /// matches the two players sat out since their last game, added up
+    pub rest: i64,
+    /// how much the match evens out the players' left/right counts, negative
+    /// if it makes them more uneven; with a `SideDisadvantage` how much it
+    /// brings the two players' disadvantage closer to the mean of all
+    /// players, times the number of players so that a change by less than
+    /// one isn't rounded away
+    pub side_balance: i64,
+    /// how much less the two played against each other than the most
+    /// played pairing
+    pub pairing: i64,
+    /// weights of the soft rules the match breaks
+    pub penalty: u32,
+    pub total: i64,
+}
+
+impl TtPlaythrough {
+    /// Scores every match the rules allow next and ranks them, matches
+    /// breaking soft rules last, then the highest total first.
+    pub fn recommend_next_matches(
+        &self,
+        weights: &RecommendWeights,
+    ) -> Vec<Recommendation> {
+        let rests = self.rests();
+        let side_counts = self.total_side_counts();
+        let disadvantages = self.disadvantages();
+        let player_count = disadvantages.len() as i64;
+        let total_disadvantage = disadvantages.values().sum::<u64>() as i64;
+        let candidates = self.candidate_matches();
+
+        let most_played_pairing = candidates
+            .iter()
+            .map(|players| self.pairing_count(*players))
+            .max()
+            .unwrap_or(0) as i64;
+
+        candidates
+            .into_iter()
+            .filter(|players| self.check_match_possible(*players))
+            .map(|players| {
+                let (left, right) = players;
+
+                let rest = (rests[&left] + rests[&right]) as i64;
+
+                let side_balance = match self.side_disadvantage() {
+                    // the worse side is fair for who had less than the
+                    // others so far
+                    Some(side_disadvantage) => {
+                        let (left_d, right_d) =
+                            side_disadvantage.at(self.matches().len());
+                        let (left_d, right_d) = (left_d as i64, right_d as i64);
+                        let before = |player: usize| {
+                            disadvantages[&player] as i64 * player_count
+                                - total_disadvantage
+                        };
+                        // the mean moves as well, everything is times the
+                        // number of players to stay with exact integers
+                        let after = |player: usize, added: i64| {
+                            before(player) + added * player_count
+                                - left_d
+                                - right_d
+                        };
+
+                        before(left).abs() + before(right).abs()
+                            - after(left, left_d).abs()
+                            - after(right, right_d).abs()
+                    }
+                    // playing left is fair for who played right more often
+                    None => {
+                        let (left_l, left_r) = side_counts[&left];
+                        let (right_l, right_r) = side_counts[&right];
+                        (left_r as i64 - left_l as i64)
+                            + (right_l as i64 - right_r as i64)
+                    }
+                };
+
+                let pairing =
+                    most_played_pairing - self.pairing_count(players) as i64;
+
+                Recommendation {
+                    players,
+                    rest,
+                    side_balance,
+                    pairing,
+                    penalty: self.match_penalty(players),
+                    total: weights.rest * rest
+                        + weights.side_balance * side_balance
+                        + weights.pairing * pairing,
+                }
+            })
+            .sorted_by_key(|r| (r.penalty, Reverse(r.total), r.players))
+            .collect_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::{Rule, SideDisadvantage};
+
+    const TEST_PLAYERS: [usize; 4] = [0, 1, 2, 3];
+
+    #[test]
+    fn test_recommend_next_matches_scores() {
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 0)).unwrap();
+
+        let recommendations =
+            playthrough.recommend_next_matches(&RecommendWeights::default());
+
+        // 1 and 3 rested the longest, 1 played right before
+        let one_vs_three = recommendations
+            .iter()
+            .find(|r| r.players == (1, 3))
+            .unwrap();
+        assert_eq!(one_vs_three.rest, 3);
+        assert_eq!(one_vs_three.side_balance, 1);
+        assert_eq!(one_vs_three.pairing, 1);
+        assert_eq!(one_vs_three.total, 5);
+        assert_eq!(recommendations[0], *one_vs_three);
+
+        let three_vs_one = recommendations
+            .iter()
+            .find(|r| r.players == (3, 1))
+            .unwrap();
+        assert_eq!(three_vs_one.side_balance, -1);
+    }
+
+    #[test]
+    fn test_recommend_next_matches_only_possible() {
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        playthrough.play_match((0, 1)).unwrap();
+
+        let recommendations =
+            playthrough.recommend_next_matches(&RecommendWeights::default());
+
+        assert!(recommendations
+            .iter()
+            .all(|r| playthrough.check_match_possible(r.players)));
+        assert!(!recommendations.iter().any(|r| r.players == (1, 0)));
+    }
+
+    #[test]
+    fn test_recommend_next_matches_weights() {
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 3)).unwrap();
+
+        // only the side balance counts: 1-0, 1-2 and 3-0 all switch both
+        // players' sides, ties are ranked by the players
+        let weights = RecommendWeights {
+            rest: 0,
+            side_balance: 1,
+            pairing: 0,
+        };
+        let recommendations = playthrough.recommend_next_matches(&weights);
+
+        assert_eq!(recommendations[0].players, (1, 0));
+        assert_eq!(recommendations[0].total, 2);
+    }
+
+    #[test]
+    fn test_recommend_next_matches_carried_sides() {
+        // 0 played left all of the last session
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .with_carried_sides(0, (6, 0))
+            .unwrap();
+
+        let recommendations =
+            playthrough.recommend_next_matches(&RecommendWeights::default());
+
+        assert_eq!(recommendations[0].players.1, 0);
+        assert_eq!(recommendations[0].side_balance, 6);
+    }
+
+    #[test]
+    fn test_recommend_next_matches_side_disadvantage() {
+        // the left side is 4 worse, 0 and 2 already had it once
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSide)
+            .with_side_disadvantage(SideDisadvantage::new(4, 0));
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 3)).unwrap();
+
+        let recommendations =
+            playthrough.recommend_next_matches(&RecommendWeights::default());
+        let score = |players| {
+            recommendations
+                .iter()
+                .find(|r| r.players == players)
+                .unwrap()
+                .side_balance
+        };
+
+        // the same gap, but 1 and 3 are below the mean and 0 and 2 above
+        assert_eq!(score((1, 3)), 0);
+        assert_eq!(score((0, 2)), -8);
+        // one of each, the left side goes to who is below
+        assert_eq!(score((1, 2)), 8);
+        assert_eq!(score((2, 1)), -16);
+        assert_eq!(recommendations[0].side_balance, 8);
+    }
+
+    #[test]
+    fn test_recommend_next_matches_small_disadvantage() {
+        // the left side is 1 worse and 0 had it, the mean is a third
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2)
+            .unwrap()
+            .with_side_disadvantage(SideDisadvantage::new(1, 0));
+        playthrough.play_match((0, 1)).unwrap();
+
+        let weights = RecommendWeights {
+            rest: 0,
+            side_balance: 1,
+            pairing: 0,
+        };
+        let recommendations = playthrough.recommend_next_matches(&weights);
+
+        // less than a whole disadvantage apart, but 2-0 still ranks first
+        assert_eq!(recommendations[0].players, (2, 0));
+        assert_eq!(recommendations[0].side_balance, 1);
+        assert!(recommendations[1..].iter().all(|r| r.side_balance == -1));
+    }
+}
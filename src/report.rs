@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::tt::TtPlaythrough;
+
+const BAR_HEIGHT: usize = 20;
+const BAR_GAP: usize = 8;
+const LABEL_WIDTH: usize = 80;
+const CHART_WIDTH: usize = 400;
+const LEFT_COLOR: &str = "#4e79a7";
+const RIGHT_COLOR: &str = "#f28e2b";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// What a report shows besides the playthrough.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReportOptions {
+    /// names to show instead of the player numbers
+    pub names: BTreeMap<usize, String>,
+    /// the configuration the schedule was generated with, shown at the end
+    pub config: Option<String>,
+}
+
+impl ReportOptions {
+    fn name(&self, player: usize) -> String {
+        match self.names.get(&player) {
+            Some(name) => name.clone(),
+            None => player.to_string(),
+        }
+    }
+}
+
+/// A player's line in the summary of a report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerSummary {
+    pub player: usize,
+    pub games: usize,
+    pub left: usize,
+    pub right: usize,
+    /// most matches sat out in a row, before the first game as well
+    pub longest_rest: usize,
+    /// see `TtPlaythrough::disadvantages`
+    pub disadvantage: u64,
+}
+
+pub fn player_summaries(playthrough: &TtPlaythrough) -> Vec<PlayerSummary> {
+    let disadvantages = playthrough.disadvantages();
+
+    playthrough
+        .side_counts()
+        .into_iter()
+        .map(|(player, (left, right))| {
+            let longest_rest = playthrough
+                .matches()
+                .split(|m| m.has_player(player))
+                .map(|rest| rest.len())
+                .max()
+                .unwrap_or(0);
+
+            PlayerSummary {
+                player,
+                games: left + right,
+                left,
+                right,
+                longest_rest,
+                disadvantage: disadvantages[&player],
+            }
+        })
+        .collect()
+}
+
+pub fn render(
+    playthrough: &TtPlaythrough,
+    format: ReportFormat,
+    options: &ReportOptions,
+) -> String {
+    match format {
+        ReportFormat::Markdown => render_markdown(playthrough, options),
+        ReportFormat::Html => render_html(playthrough, options),
+    }
+}
+
+/// Schedule and player summary as Markdown tables, for pasting into a wiki
+/// or chat.
+pub fn render_markdown(
+    playthrough: &TtPlaythrough,
+    options: &ReportOptions,
+) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "## Schedule\n").unwrap();
+    writeln!(out, "| Round | Left | Right |").unwrap();
+    writeln!(out, "|------:|-----:|------:|").unwrap();
+    for (index, m) in playthrough.matches().iter().enumerate() {
+        writeln!(
+            out,
+            "| {} | {} | {} |",
+            index + 1,
+            escape_markdown(&options.name(m.left())),
+            escape_markdown(&options.name(m.right()))
+        )
+        .unwrap();
+    }
+
+    // the disadvantage only means something if the sides differ
+    let weighted = playthrough.side_disadvantage().is_some();
+
+    writeln!(out, "\n## Players\n").unwrap();
+    write!(out, "| Player | Games | Left | Right | Longest rest |").unwrap();
+    writeln!(out, "{}", if weighted { " Disadvantage |" } else { "" }).unwrap();
+    write!(out, "|-------:|------:|-----:|------:|-------------:|").unwrap();
+    writeln!(out, "{}", if weighted { "-------------:|" } else { "" }).unwrap();
+    for s in player_summaries(playthrough) {
+        write!(
+            out,
+            "| {} | {} | {} | {} | {} |",
+            escape_markdown(&options.name(s.player)),
+            s.games,
+            s.left,
+            s.right,
+            s.longest_rest
+        )
+        .unwrap();
+        if weighted {
+            writeln!(out, " {} |", s.disadvantage).unwrap();
+        } else {
+            writeln!(out).unwrap();
+        }
+    }
+
+    if let Some(config) = &options.config {
+        writeln!(out, "\n## Config\n\n```toml\n{}```", config).unwrap();
+    }
+
+    out
+}
+
+/// A self-contained HTML page with the schedule, the player summary and the
+/// side balance as an inline SVG bar chart.
+pub fn render_html(
+    playthrough: &TtPlaythrough,
+    options: &ReportOptions,
+) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>tttable report</title>").unwrap();
+    writeln!(
+        out,
+        "<style>table {{ border-collapse: collapse; }} \
+         th, td {{ border: 1px solid #999; padding: 2px 8px; \
+         text-align: right; }}</style>"
+    )
+    .unwrap();
+    writeln!(out, "</head>\n<body>").unwrap();
+
+    writeln!(out, "<h2>Schedule</h2>\n<table>").unwrap();
+    writeln!(out, "<tr><th>Round</th><th>Left</th><th>Right</th></tr>")
+        .unwrap();
+    for (index, m) in playthrough.matches().iter().enumerate() {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            index + 1,
+            escape_html(&options.name(m.left())),
+            escape_html(&options.name(m.right()))
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+
+    let summaries = player_summaries(playthrough);
+    let weighted = playthrough.side_disadvantage().is_some();
+
+    writeln!(out, "<h2>Players</h2>\n<table>").unwrap();
+    writeln!(
+        out,
+        "<tr><th>Player</th><th>Games</th><th>Left</th><th>Right</th>\
+         <th>Longest rest</th>{}</tr>",
+        if weighted {
+            "<th>Disadvantage</th>"
+        } else {
+            ""
+        }
+    )
+    .unwrap();
+    for s in summaries.iter() {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+            escape_html(&options.name(s.player)),
+            s.games,
+            s.left,
+            s.right,
+            s.longest_rest,
+            if weighted {
+                format!("<td>{}</td>", s.disadvantage)
+            } else {
+                String::new()
+            }
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+
+    writeln!(out, "<h2>Side balance</h2>").unwrap();
+    out.push_str(&side_balance_chart(&summaries, options));
+
+    if let Some(config) = &options.config {
+        writeln!(out, "<h2>Config</h2>\n<pre>{}</pre>", escape_html(config))
+            .unwrap();
+    }
+    writeln!(out, "</body>\n</html>").unwrap();
+
+    out
+}
+
+/// one horizontal bar per player, the left games in one color followed by
+/// the right games in another
+fn side_balance_chart(
+    summaries: &[PlayerSummary],
+    options: &ReportOptions,
+) -> String {
+    let most_games = summaries.iter().map(|s| s.games).max().unwrap_or(0);
+    let scale = |games: usize| games * CHART_WIDTH / most_games.max(1);
+
+    let height = summaries.len() * (BAR_HEIGHT + BAR_GAP) + BAR_GAP;
+    let label = |player: usize| match options.names.get(&player) {
+        Some(name) => name.clone(),
+        None => format!("Player {player}"),
+    };
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" \
+         height=\"{height}\">",
+        LABEL_WIDTH + CHART_WIDTH + BAR_GAP
+    )
+    .unwrap();
+
+    for (index, s) in summaries.iter().enumerate() {
+        let y = BAR_GAP + index * (BAR_HEIGHT + BAR_GAP);
+        let (left, right) = (scale(s.left), scale(s.right));
+
+        writeln!(
+            out,
+            "<text x=\"0\" y=\"{}\">{}</text>",
+            y + BAR_HEIGHT * 3 / 4,
+            escape_html(&label(s.player))
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "<rect x=\"{LABEL_WIDTH}\" y=\"{y}\" width=\"{left}\" \
+             height=\"{BAR_HEIGHT}\" fill=\"{LEFT_COLOR}\">\
+             <title>{} left</title></rect>",
+            s.left
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{y}\" width=\"{right}\" \
+             height=\"{BAR_HEIGHT}\" fill=\"{RIGHT_COLOR}\">\
+             <title>{} right</title></rect>",
+            LABEL_WIDTH + left,
+            s.right
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "</svg>").unwrap();
+
+    out
+}
+
+/// Keeps a name inside its table cell: a `|` would start a new cell and a
+/// line break would end the row.
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_playthrough() -> TtPlaythrough {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((1, 2)).unwrap();
+        playthrough.play_match((2, 0)).unwrap();
+        playthrough
+    }
+
+    #[test]
+    fn test_player_summaries() {
+        let summaries = player_summaries(&test_playthrough());
+
+        assert_eq!(
+            summaries[2],
+            PlayerSummary {
+                player: 2,
+                games: 2,
+                left: 1,
+                right: 1,
+                longest_rest: 1,
+                disadvantage: 0,
+            }
+        );
+        assert_eq!(summaries[0].longest_rest, 1);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let markdown =
+            render_markdown(&test_playthrough(), &ReportOptions::default());
+
+        assert!(markdown.contains("| 1 | 0 | 1 |\n| 2 | 1 | 2 |\n"));
+        assert!(markdown.contains("| 1 | 2 | 1 | 1 | 1 |"));
+        assert!(!markdown.contains("## Config"));
+    }
+
+    #[test]
+    fn test_render_markdown_names_and_config() {
+        let options = ReportOptions {
+            names: BTreeMap::from([(0, "Alice".to_string())]),
+            config: Some("players = [\"Alice\"]\n".to_string()),
+        };
+
+        let markdown = render_markdown(&test_playthrough(), &options);
+
+        assert!(markdown.contains("| 1 | Alice | 1 |"));
+        assert!(markdown.ends_with("```toml\nplayers = [\"Alice\"]\n```\n"));
+    }
+
+    #[test]
+    fn test_render_markdown_escapes_names() {
+        let options = ReportOptions {
+            names: BTreeMap::from([(0, "A|B\nC".to_string())]),
+            config: None,
+        };
+
+        let markdown = render_markdown(&test_playthrough(), &options);
+
+        assert!(markdown.contains("| 1 | A\\|B C | 1 |\n"));
+        assert!(markdown.contains("| A\\|B C | 2 | 1 | 1 | 1 |\n"));
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(&test_playthrough(), &ReportOptions::default());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<tr><td>3</td><td>2</td><td>0</td></tr>"));
+        assert_eq!(html.matches("<rect").count(), 6);
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+}
@@ -0,0 +1,296 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+
+use crate::recommend::RecommendWeights;
+use crate::tt::{Rule, TtMatch, TtPlaythrough};
+
+/// search steps before falling back to a greedy order breaking rules
+const MAX_SEARCH_STEPS: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundRobinKind {
+    /// every pairing plays once, on whichever sides balance the players best
+    Single,
+    /// every pairing plays twice, once in each orientation
+    Double,
+}
+
+/// A match of the round robin which breaks rules because no order of the
+/// remaining matches could avoid it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// position in `RoundRobin::matches`
+    pub index: usize,
+    pub tt_match: TtMatch,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundRobin {
+    pub matches: Vec<TtMatch>,
+    pub violations: Vec<Violation>,
+    /// the given playthrough with the round robin played
+    pub playthrough: TtPlaythrough,
+}
+
+/// Orders a complete round robin over the playthrough's players so that it
+/// keeps all of the playthrough's rules if that's possible, balancing every
+/// player's left and right games.
+///
+/// Unlike the circle method this doesn't produce rounds of parallel matches
+/// but one sequence for a single table.
+pub fn round_robin(
+    playthrough: &TtPlaythrough,
+    kind: RoundRobinKind,
+) -> RoundRobin {
+    let remaining = remaining_matches(playthrough.players(), kind);
+
+    let mut search = Search { kind, steps: 0 };
+    let mut current = playthrough.clone();
+
+    if search.find(&mut current, &mut remaining.clone()) {
+        let matches = current.matches()[playthrough.matches().len()..].to_vec();
+
+        return RoundRobin {
+            matches,
+            violations: vec![],
+            playthrough: current,
+        };
+    }
+
+    log::debug!(
+        "No round robin keeps all rules after {} steps, ordering greedily",
+        search.steps
+    );
+
+    greedy(playthrough, kind, remaining)
+}
+
+/// pairings still to play, unordered for `Single` and ordered for `Double`
+fn remaining_matches(
+    players: &[usize],
+    kind: RoundRobinKind,
+) -> BTreeSet<(usize, usize)> {
+    players
+        .iter()
+        .cartesian_product(players.iter())
+        .filter(|(a, b)| match kind {
+            RoundRobinKind::Single => a < b,
+            RoundRobinKind::Double => a != b,
+        })
+        .map(|(a, b)| (*a, *b))
+        .collect()
+}
+
+/// the entry in the remaining matches a match of the given orientation plays
+fn remaining_key(
+    players: (usize, usize),
+    kind: RoundRobinKind,
+) -> (usize, usize) {
+    match kind {
+        RoundRobinKind::Single => {
+            (players.0.min(players.1), players.0.max(players.1))
+        }
+        RoundRobinKind::Double => players,
+    }
+}
+
+struct Search {
+    kind: RoundRobinKind,
+    steps: usize,
+}
+
+impl Search {
+    fn find(
+        &mut self,
+        playthrough: &mut TtPlaythrough,
+        remaining: &mut BTreeSet<(usize, usize)>,
+    ) -> bool {
+        if remaining.is_empty() {
+            return true;
+        }
+
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS
+            || !self.can_balance(playthrough, remaining)
+        {
+            return false;
+        }
+
+        // fairest first, that's what balances the sides
+        let options = playthrough
+            .recommend_next_matches(&RecommendWeights::default())
+            .into_iter()
+            .map(|recommendation| recommendation.players)
+            .filter(|players| {
+                remaining.contains(&remaining_key(*players, self.kind))
+            })
+            .filter(|players| playthrough.violated_rules(*players).is_empty())
+            .collect_vec();
+
+        for players in options {
+            let key = remaining_key(players, self.kind);
+
+            let mut next = playthrough.clone();
+            next.force_match(players).expect("a remaining pairing");
+            remaining.remove(&key);
+
+            if self.find(&mut next, remaining) {
+                *playthrough = next;
+                return true;
+            }
+
+            remaining.insert(key);
+        }
+
+        false
+    }
+
+    /// whether every player can still end up with at most one game more on
+    /// one side than on the other, always true for `Double`
+    fn can_balance(
+        &self,
+        playthrough: &TtPlaythrough,
+        remaining: &BTreeSet<(usize, usize)>,
+    ) -> bool {
+        if self.kind == RoundRobinKind::Double {
+            return true;
+        }
+
+        playthrough
+            .side_counts()
+            .into_iter()
+            .all(|(player, (left, right))| {
+                let games_left = remaining
+                    .iter()
+                    .filter(|(a, b)| *a == player || *b == player)
+                    .count();
+
+                left.abs_diff(right) <= games_left + 1
+            })
+    }
+}
+
+/// plays the remaining matches one by one, always the one breaking the
+/// fewest rules and then balancing the sides best
+fn greedy(
+    playthrough: &TtPlaythrough,
+    kind: RoundRobinKind,
+    mut remaining: BTreeSet<(usize, usize)>,
+) -> RoundRobin {
+    let mut current = playthrough.clone();
+    let mut matches = vec![];
+    let mut violations = vec![];
+
+    while !remaining.is_empty() {
+        let side_counts = current.side_counts();
+
+        let options = remaining.iter().flat_map(|(a, b)| match kind {
+            RoundRobinKind::Single => vec![(*a, *b), (*b, *a)],
+            RoundRobinKind::Double => vec![(*a, *b)],
+        });
+
+        let (players, rules) = options
+            .map(|players| {
+                let rules = current.violated_rules(players);
+                (players, rules)
+            })
+            .min_by_key(|(players, rules)| {
+                let (left_l, left_r) = side_counts[&players.0];
+                let (right_l, right_r) = side_counts[&players.1];
+                let balance = (left_r as i64 - left_l as i64)
+                    + (right_l as i64 - right_r as i64);
+
+                (rules.len(), Reverse(balance), *players)
+            })
+            .expect("remaining is not empty");
+
+        if !rules.is_empty() {
+            violations.push(Violation {
+                index: matches.len(),
+                tt_match: TtMatch::new(players.0, players.1),
+                rules,
+            });
+        }
+
+        current.force_match(players).expect("a remaining pairing");
+        matches.push(TtMatch::new(players.0, players.1));
+        remaining.remove(&remaining_key(players, kind));
+    }
+
+    RoundRobin {
+        matches,
+        violations,
+        playthrough: current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_complete(
+        round_robin: &RoundRobin,
+        players: &[usize],
+        kind: RoundRobinKind,
+    ) {
+        let played: BTreeSet<(usize, usize)> = round_robin
+            .matches
+            .iter()
+            .map(|m| remaining_key((m.left(), m.right()), kind))
+            .collect();
+
+        assert_eq!(played, remaining_matches(players, kind));
+        assert_eq!(played.len(), round_robin.matches.len());
+    }
+
+    #[test]
+    fn test_single_round_robin_keeps_rules_and_balances_sides() {
+        let players = vec![0, 1, 2, 3, 4];
+        let playthrough = TtPlaythrough::new(players.clone(), 2).unwrap();
+
+        let round_robin = round_robin(&playthrough, RoundRobinKind::Single);
+
+        assert_complete(&round_robin, &players, RoundRobinKind::Single);
+        assert!(round_robin.violations.is_empty());
+
+        for (left, right) in round_robin.playthrough.side_counts().into_values()
+        {
+            assert_eq!(left + right, 4);
+            assert!(left.abs_diff(right) <= 1);
+        }
+    }
+
+    #[test]
+    fn test_double_round_robin() {
+        let players = vec![0, 1, 2, 3];
+        let playthrough = TtPlaythrough::new(players.clone(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+
+        let round_robin = round_robin(&playthrough, RoundRobinKind::Double);
+
+        assert_complete(&round_robin, &players, RoundRobinKind::Double);
+        assert!(round_robin.violations.is_empty());
+
+        for (left, right) in round_robin.playthrough.side_counts().into_values()
+        {
+            assert_eq!((left, right), (3, 3));
+        }
+    }
+
+    #[test]
+    fn test_round_robin_reports_unavoidable_violations() {
+        // with rule 4 three players can only play three matches in a row
+        let players = vec![0, 1, 2];
+        let playthrough = TtPlaythrough::new(players.clone(), 2).unwrap();
+
+        let round_robin = round_robin(&playthrough, RoundRobinKind::Double);
+
+        assert_complete(&round_robin, &players, RoundRobinKind::Double);
+        assert!(!round_robin.violations.is_empty());
+        assert!(round_robin.violations.iter().all(|v| !v.rules.is_empty()));
+    }
+}
@@ -0,0 +1,376 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::recommend::RecommendWeights;
+use crate::report::{self, ReportFormat, ReportOptions};
+use crate::tt::TtPlaythrough;
+
+/// Requests with a larger body are refused.
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// A playthrough and the results of its matches saved as JSON in a file,
+/// written again after every change so the schedule survives restarts of
+/// the server.
+#[derive(Debug)]
+pub struct Session {
+    path: PathBuf,
+    file: SessionFile,
+}
+
+/// the saved playthrough with the winners next to its fields, so files of
+/// a bare playthrough load as well
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionFile {
+    #[serde(flatten)]
+    playthrough: TtPlaythrough,
+    /// winner of every match, `None` where the result isn't known
+    #[serde(default)]
+    winners: Vec<Option<usize>>,
+}
+
+impl SessionFile {
+    /// every winner played their match, missing results are unknown
+    fn checked(mut self) -> Result<Self, String> {
+        let matches = self.playthrough.matches();
+
+        if self.winners.len() > matches.len() {
+            return Err("more winners than matches".to_string());
+        }
+        for (m, winner) in matches.iter().zip(self.winners.iter()) {
+            if winner.is_some_and(|winner| !m.has_player(winner)) {
+                return Err(format!("the winner of {m} didn't play it"));
+            }
+        }
+
+        self.winners.resize(matches.len(), None);
+        Ok(self)
+    }
+}
+
+/// the body of `POST /matches`
+#[derive(Deserialize)]
+struct PlayedMatch {
+    left: usize,
+    right: usize,
+    #[serde(default)]
+    winner: Option<usize>,
+}
+
+impl Session {
+    /// loads the session file, or starts it with `new` if there is none yet
+    pub fn open(
+        path: impl Into<PathBuf>,
+        new: impl FnOnce() -> TtPlaythrough,
+    ) -> io::Result<Self> {
+        let path = path.into();
+
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+
+        let file = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str::<SessionFile>(&json)
+                .map_err(|error| invalid(error.to_string()))?
+                .checked()
+                .map_err(invalid)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                SessionFile {
+                    playthrough: new(),
+                    winners: vec![],
+                }
+            }
+            Err(error) => return Err(error),
+        };
+
+        let session = Self { path, file };
+        session.save(&session.file)?;
+
+        Ok(session)
+    }
+
+    pub fn playthrough(&self) -> &TtPlaythrough {
+        &self.file.playthrough
+    }
+
+    /// the winner of every match in the playthrough, `None` if unknown
+    pub fn winners(&self) -> &[Option<usize>] {
+        &self.file.winners
+    }
+
+    /// written to a temporary file first so a crash never leaves half a
+    /// session behind
+    fn save(&self, file: &SessionFile) -> io::Result<()> {
+        let json =
+            serde_json::to_string_pretty(file).expect("a session serializes");
+        let temporary = self.path.with_extension("tmp");
+
+        fs::write(&temporary, json)?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    /// Answers one API request, see the README for the endpoints.
+    pub fn handle(&mut self, method: &Method, url: &str, body: &str) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+        match (method, path) {
+            (Method::Get, "/state") => Reply::json(200, &self.file),
+            (Method::Get, "/next") => {
+                let recommendations = self
+                    .file
+                    .playthrough
+                    .recommend_next_matches(&RecommendWeights::default());
+                Reply::json(200, &recommendations.first())
+            }
+            (Method::Get, "/report") => {
+                let format = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("format="))
+                    .unwrap_or("md");
+                let Some(format) = ReportFormat::from_name(format) else {
+                    return Reply::error(400, "format is md or html");
+                };
+
+                Reply {
+                    status: 200,
+                    content_type: match format {
+                        ReportFormat::Markdown => "text/markdown",
+                        ReportFormat::Html => "text/html",
+                    },
+                    body: report::render(
+                        &self.file.playthrough,
+                        format,
+                        &ReportOptions::default(),
+                    ),
+                }
+            }
+            (Method::Post, "/matches") => {
+                let played: PlayedMatch = match serde_json::from_str(body) {
+                    Ok(played) => played,
+                    Err(error) => return Reply::error(400, &error.to_string()),
+                };
+
+                let players = (played.left, played.right);
+                if played.winner.is_some_and(|winner| {
+                    winner != players.0 && winner != players.1
+                }) {
+                    return Reply::error(422, "the winner has to play");
+                }
+
+                let mut file = self.file.clone();
+                if let Err(error) = file.playthrough.play_match(players) {
+                    return Reply::error(422, &error.to_string());
+                }
+                file.winners.push(played.winner);
+
+                let reply = Reply::json(200, &file);
+                self.saved(file, reply)
+            }
+            (Method::Post, "/undo") => {
+                let mut file = self.file.clone();
+                let Some(tt_match) = file.playthrough.undo_match() else {
+                    return Reply::error(409, "no match to undo");
+                };
+                file.winners.pop();
+
+                self.saved(file, Reply::json(200, &tt_match))
+            }
+            _ => Reply::error(404, "unknown endpoint"),
+        }
+    }
+
+    /// keeps the changed `file` only if it could be saved, so the session
+    /// in memory never differs from the one on disk
+    fn saved(&mut self, file: SessionFile, reply: Reply) -> Reply {
+        match self.save(&file) {
+            Ok(()) => {
+                self.file = file;
+                reply
+            }
+            Err(error) => {
+                Reply::error(500, &format!("can't save the session: {error}"))
+            }
+        }
+    }
+}
+
+/// An API answer before it is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Reply {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_string(value).expect("replies serialize"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+/// Answers the server's requests one after another, forever.
+pub fn serve(server: &Server, session: &mut Session) {
+    for request in server.incoming_requests() {
+        respond(request, session);
+    }
+}
+
+fn respond(mut request: Request, session: &mut Session) {
+    let mut body = String::new();
+    // one byte more than allowed tells a body which is too large
+    let mut reader = request.as_reader().take(MAX_BODY_BYTES + 1);
+    let reply = match reader.read_to_string(&mut body) {
+        Ok(length) if length as u64 > MAX_BODY_BYTES => {
+            Reply::error(413, "the body is too large")
+        }
+        Ok(_) => session.handle(request.method(), request.url(), &body),
+        Err(error) => Reply::error(400, &error.to_string()),
+    };
+
+    log::debug!("{} {} -> {}", request.method(), request.url(), reply.status);
+
+    let content_type = Header::from_bytes("Content-Type", reply.content_type)
+        .expect("a valid header");
+    let response = Response::from_string(reply.body)
+        .with_status_code(reply.status)
+        .with_header(content_type);
+
+    if let Err(error) = request.respond(response) {
+        log::warn!("Couldn't answer a request: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::TtMatch;
+
+    fn session(name: &str) -> Session {
+        let path = std::env::temp_dir()
+            .join(format!("tttable-{}-{name}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        Session::open(path, || TtPlaythrough::new(vec![0, 1, 2], 2).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_play_undo_and_reload() {
+        let mut session = session("play");
+
+        let reply = session.handle(
+            &Method::Post,
+            "/matches",
+            r#"{"left":0,"right":1}"#,
+        );
+        assert_eq!(reply.status, 200);
+
+        // rule 1: the same players can't play again right away
+        let reply = session.handle(
+            &Method::Post,
+            "/matches",
+            r#"{"left":1,"right":0}"#,
+        );
+        assert_eq!(reply.status, 422);
+        assert_eq!(reply.body, r#"{"error":"match 1 - 0 breaks rule 1"}"#);
+
+        let reopened = Session::open(&session.path, || unreachable!()).unwrap();
+        assert_eq!(reopened.playthrough().matches(), &[TtMatch::new(0, 1)]);
+        assert_eq!(reopened.winners(), [None]);
+
+        let reply = session.handle(&Method::Post, "/undo", "");
+        assert_eq!(reply.body, r#"{"left":0,"right":1}"#);
+        assert_eq!(session.handle(&Method::Post, "/undo", "").status, 409);
+
+        fs::remove_file(&session.path).unwrap();
+    }
+
+    #[test]
+    fn test_winners_are_saved() {
+        let mut session = session("winners");
+
+        let reply = session.handle(
+            &Method::Post,
+            "/matches",
+            r#"{"left":0,"right":1,"winner":2}"#,
+        );
+        assert_eq!(reply.status, 422);
+        assert!(session.playthrough().matches().is_empty());
+
+        let reply = session.handle(
+            &Method::Post,
+            "/matches",
+            r#"{"left":0,"right":1,"winner":1}"#,
+        );
+        assert_eq!(reply.status, 200);
+        session.handle(&Method::Post, "/matches", r#"{"left":2,"right":0}"#);
+
+        let reopened = Session::open(&session.path, || unreachable!()).unwrap();
+        assert_eq!(reopened.winners(), [Some(1), None]);
+
+        session.handle(&Method::Post, "/undo", "");
+        assert_eq!(session.winners(), [Some(1)]);
+
+        fs::remove_file(&session.path).unwrap();
+    }
+
+    #[test]
+    fn test_open_a_saved_playthrough() {
+        let path = std::env::temp_dir()
+            .join(format!("tttable-{}-bare.json", std::process::id()));
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+        fs::write(&path, serde_json::to_string(&playthrough).unwrap()).unwrap();
+
+        let session = Session::open(&path, || unreachable!()).unwrap();
+        assert_eq!(session.playthrough().matches(), playthrough.matches());
+        assert_eq!(session.winners(), [None]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_save_keeps_the_session() {
+        let mut session = session("unsaved");
+        let path = session.path.clone();
+        // a directory in the way of the temporary file
+        let temporary = path.with_extension("tmp");
+        fs::create_dir_all(&temporary).unwrap();
+
+        let reply = session.handle(
+            &Method::Post,
+            "/matches",
+            r#"{"left":0,"right":1}"#,
+        );
+        assert_eq!(reply.status, 500);
+        assert!(session.playthrough().matches().is_empty());
+
+        fs::remove_dir(&temporary).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let mut session = session("bad");
+
+        assert_eq!(session.handle(&Method::Get, "/nothing", "").status, 404);
+        assert_eq!(session.handle(&Method::Post, "/matches", "{").status, 400);
+        assert_eq!(
+            session
+                .handle(&Method::Get, "/report?format=pdf", "")
+                .status,
+            400
+        );
+
+        fs::remove_file(&session.path).unwrap();
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::tt::TtMatch;
+
+/// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 40.0;
+const TITLE_SIZE: f32 = 16.0;
+const TEXT_SIZE: f32 = 11.0;
+const HEADER_HEIGHT: f32 = 50.0;
+const ROW_HEIGHT: f32 = 28.0;
+const BOX_SIZE: f32 = 20.0;
+
+/// x positions of the columns
+const NUMBER_X: f32 = MARGIN + 20.0;
+const LEFT_X: f32 = MARGIN + 200.0;
+const SCORE_X: f32 = PAGE_WIDTH / 2.0;
+const RIGHT_X: f32 = PAGE_WIDTH - MARGIN - 200.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SheetFormat {
+    Svg,
+    Pdf,
+}
+
+impl SheetFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "svg" => Some(Self::Svg),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// What a sheet is drawn from, in points from the top left corner.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        anchor: Anchor,
+        text: String,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+}
+
+/// Printable sheet of a schedule: one row per match with the players' names
+/// on their sides and empty boxes for the score.
+#[derive(Debug, Clone)]
+pub struct ScoreSheet {
+    pub title: String,
+    /// names to print instead of "Player N"
+    pub names: BTreeMap<usize, String>,
+    /// the configuration the schedule was generated with, embedded as a
+    /// comment
+    pub config: Option<String>,
+}
+
+impl ScoreSheet {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            names: BTreeMap::new(),
+            config: None,
+        }
+    }
+
+    pub fn with_names(mut self, names: BTreeMap<usize, String>) -> Self {
+        self.names = names;
+        self
+    }
+
+    pub fn with_config(mut self, config: String) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn render(&self, matches: &[TtMatch], format: SheetFormat) -> Vec<u8> {
+        match format {
+            SheetFormat::Svg => self.render_svg(matches).into_bytes(),
+            SheetFormat::Pdf => self.render_pdf(matches),
+        }
+    }
+
+    /// a single SVG as long as the schedule needs
+    pub fn render_svg(&self, matches: &[TtMatch]) -> String {
+        let height =
+            MARGIN * 2.0 + HEADER_HEIGHT + ROW_HEIGHT * matches.len() as f32;
+        let mut out = String::new();
+
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             width=\"{PAGE_WIDTH}pt\" height=\"{height}pt\" \
+             viewBox=\"0 0 {PAGE_WIDTH} {height}\" \
+             font-family=\"Helvetica, Arial, sans-serif\">"
+        )
+        .unwrap();
+        if let Some(config) = &self.config {
+            // "--" ends a comment early
+            writeln!(out, "<!-- config\n{}-->", config.replace("--", "- -"))
+                .unwrap();
+        }
+        writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>")
+            .unwrap();
+
+        for shape in self.layout(matches, 0, None) {
+            match shape {
+                Shape::Text {
+                    x,
+                    y,
+                    size,
+                    anchor,
+                    text,
+                } => {
+                    let anchor = match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    };
+                    writeln!(
+                        out,
+                        "<text x=\"{x}\" y=\"{y}\" font-size=\"{size}\" \
+                         text-anchor=\"{anchor}\">{}</text>",
+                        escape_xml(&text)
+                    )
+                    .unwrap();
+                }
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => writeln!(
+                    out,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{width}\" \
+                     height=\"{height}\" fill=\"none\" stroke=\"black\"/>"
+                )
+                .unwrap(),
+                Shape::Line { x1, y1, x2, y2 } => writeln!(
+                    out,
+                    "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" \
+                     stroke=\"#999\" stroke-width=\"0.5\"/>"
+                )
+                .unwrap(),
+            }
+        }
+
+        writeln!(out, "</svg>").unwrap();
+
+        out
+    }
+
+    /// A4 pages, written directly as PDF with the built-in Helvetica font
+    pub fn render_pdf(&self, matches: &[TtMatch]) -> Vec<u8> {
+        let rows_per_page = ((PAGE_HEIGHT - MARGIN * 2.0 - HEADER_HEIGHT)
+            / ROW_HEIGHT) as usize;
+
+        let pages = if matches.is_empty() {
+            vec![self.layout(&[], 0, Some(1))]
+        } else {
+            let page_count = matches.len().div_ceil(rows_per_page);
+            matches
+                .chunks(rows_per_page)
+                .enumerate()
+                .map(|(index, rows)| {
+                    let first = index * rows_per_page;
+                    let page = (page_count > 1).then_some(index + 1);
+                    self.layout(rows, first, page)
+                })
+                .collect()
+        };
+
+        let contents = pages.iter().map(|shapes| pdf_content(shapes)).collect();
+
+        write_pdf(contents, self.config.as_deref())
+    }
+
+    fn name(&self, player: usize) -> String {
+        match self.names.get(&player) {
+            Some(name) => name.clone(),
+            None => format!("Player {player}"),
+        }
+    }
+
+    /// the title, column headers and a row for every match, numbered from
+    /// `first + 1`
+    fn layout(
+        &self,
+        matches: &[TtMatch],
+        first: usize,
+        page: Option<usize>,
+    ) -> Vec<Shape> {
+        let text = |x, y, size, anchor, text: String| Shape::Text {
+            x,
+            y,
+            size,
+            anchor,
+            text,
+        };
+
+        let title = match page {
+            Some(page) => format!("{} ({page})", self.title),
+            None => self.title.clone(),
+        };
+        let header_y = MARGIN + HEADER_HEIGHT - 8.0;
+
+        let mut shapes = vec![
+            text(
+                MARGIN,
+                MARGIN + TITLE_SIZE,
+                TITLE_SIZE,
+                Anchor::Start,
+                title,
+            ),
+            text(NUMBER_X, header_y, TEXT_SIZE, Anchor::End, "#".into()),
+            text(LEFT_X, header_y, TEXT_SIZE, Anchor::End, "Left".into()),
+            text(SCORE_X, header_y, TEXT_SIZE, Anchor::Middle, "Score".into()),
+            text(RIGHT_X, header_y, TEXT_SIZE, Anchor::Start, "Right".into()),
+        ];
+
+        for (index, tt_match) in matches.iter().enumerate() {
+            let top = MARGIN + HEADER_HEIGHT + ROW_HEIGHT * index as f32;
+            let baseline = top + (ROW_HEIGHT + TEXT_SIZE) / 2.0 - 2.0;
+            let box_y = top + (ROW_HEIGHT - BOX_SIZE) / 2.0;
+
+            shapes.extend([
+                Shape::Line {
+                    x1: MARGIN,
+                    y1: top,
+                    x2: PAGE_WIDTH - MARGIN,
+                    y2: top,
+                },
+                text(
+                    NUMBER_X,
+                    baseline,
+                    TEXT_SIZE,
+                    Anchor::End,
+                    (first + index + 1).to_string(),
+                ),
+                text(
+                    LEFT_X,
+                    baseline,
+                    TEXT_SIZE,
+                    Anchor::End,
+                    self.name(tt_match.left()),
+                ),
+                Shape::Rect {
+                    x: SCORE_X - 8.0 - BOX_SIZE,
+                    y: box_y,
+                    width: BOX_SIZE,
+                    height: BOX_SIZE,
+                },
+                text(SCORE_X, baseline, TEXT_SIZE, Anchor::Middle, ":".into()),
+                Shape::Rect {
+                    x: SCORE_X + 8.0,
+                    y: box_y,
+                    width: BOX_SIZE,
+                    height: BOX_SIZE,
+                },
+                text(
+                    RIGHT_X,
+                    baseline,
+                    TEXT_SIZE,
+                    Anchor::Start,
+                    self.name(tt_match.right()),
+                ),
+            ]);
+        }
+
+        shapes
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Helvetica widths are only needed to align text, an average is close
+/// enough for names and numbers
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * 0.55
+}
+
+/// Latin-1 string literal for the built-in fonts, other characters become
+/// '?'
+fn pdf_string(text: &str) -> String {
+    let mut out = String::from("(");
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => write!(out, "\\{:03o}", c as u32).unwrap(),
+            _ => out.push('?'),
+        }
+    }
+
+    out.push(')');
+    out
+}
+
+/// the page's content stream, PDF has its origin at the bottom left
+fn pdf_content(shapes: &[Shape]) -> String {
+    let mut out = String::new();
+
+    for shape in shapes {
+        match shape {
+            Shape::Text {
+                x,
+                y,
+                size,
+                anchor,
+                text,
+            } => {
+                let x = match anchor {
+                    Anchor::Start => *x,
+                    Anchor::Middle => x - text_width(text, *size) / 2.0,
+                    Anchor::End => x - text_width(text, *size),
+                };
+                writeln!(
+                    out,
+                    "BT /F1 {size} Tf {x:.1} {:.1} Td {} Tj ET",
+                    PAGE_HEIGHT - y,
+                    pdf_string(text)
+                )
+                .unwrap();
+            }
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => writeln!(
+                out,
+                "0 G 1 w {x:.1} {:.1} {width:.1} {height:.1} re S",
+                PAGE_HEIGHT - y - height
+            )
+            .unwrap(),
+            Shape::Line { x1, y1, x2, y2 } => writeln!(
+                out,
+                "0.6 G 0.5 w {x1:.1} {:.1} m {x2:.1} {:.1} l S",
+                PAGE_HEIGHT - y1,
+                PAGE_HEIGHT - y2
+            )
+            .unwrap(),
+        }
+    }
+
+    out
+}
+
+/// A minimal PDF 1.4 file: catalog, page tree, one font and a page with its
+/// content stream per entry of `contents`, `config` as comment lines after
+/// the header.
+fn write_pdf(contents: Vec<String>, config: Option<&str>) -> Vec<u8> {
+    // 1 catalog, 2 pages, 3 font, then page and content objects in turns
+    let page_ids = (0..contents.len()).map(|i| 4 + i * 2).collect::<Vec<_>>();
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            page_ids.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica \
+         /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+
+    for (page_id, content) in page_ids.iter().zip(contents) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} \
+             {PAGE_HEIGHT}] /Resources << /Font << /F1 3 0 R >> >> \
+             /Contents {} 0 R >>",
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    for line in config.into_iter().flat_map(str::lines) {
+        writeln!(out, "% {line}").unwrap();
+    }
+    let mut offsets = vec![];
+
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        write!(out, "{} 0 obj\n{object}\nendobj\n", index + 1).unwrap();
+    }
+
+    let xref = out.len();
+    writeln!(out, "xref\n0 {}", objects.len() + 1).unwrap();
+    writeln!(out, "0000000000 65535 f ").unwrap();
+    for offset in offsets {
+        writeln!(out, "{offset:010} 00000 n ").unwrap();
+    }
+    write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )
+    .unwrap();
+
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_matches(count: usize) -> Vec<TtMatch> {
+        (0..count)
+            .map(|i| TtMatch::new(i % 3, (i + 1) % 3))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_svg_names_on_their_sides() {
+        let names = BTreeMap::from([(0, "Alice".to_string())]);
+        let sheet = ScoreSheet::new("Evening").with_names(names);
+
+        let svg = sheet.render_svg(&[TtMatch::new(0, 1)]);
+
+        assert!(svg.contains("text-anchor=\"end\">Alice</text>"));
+        assert!(svg.contains("text-anchor=\"start\">Player 1</text>"));
+        // two score boxes next to the background
+        assert_eq!(svg.matches("<rect").count(), 3);
+    }
+
+    #[test]
+    fn test_render_pdf_pages() {
+        let sheet = ScoreSheet::new("Evening");
+
+        let pdf =
+            String::from_utf8(sheet.render_pdf(&test_matches(40))).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/Count 2"));
+        assert!(pdf.contains("(Evening \\(2\\)) Tj"));
+        assert_eq!(pdf.matches(" re S").count(), 80);
+    }
+
+    #[test]
+    fn test_pdf_xref_offsets() {
+        let sheet = ScoreSheet::new("Evening")
+            .with_config("players = [\"Jörg\", \"Bob\"]\n".to_string());
+        let pdf = sheet.render_pdf(&test_matches(3));
+        let pdf = String::from_utf8(pdf).unwrap();
+
+        let xref = pdf.find("xref\n").unwrap();
+        let offsets = pdf[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(offsets.len(), 5);
+        for (index, offset) in offsets.into_iter().enumerate() {
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+        assert!(pdf.contains(&format!("startxref\n{xref}\n")));
+        assert!(pdf.contains("\n% players = [\"Jörg\", \"Bob\"]\n"));
+    }
+
+    #[test]
+    fn test_pdf_string() {
+        assert_eq!(pdf_string("a (b) \\"), "(a \\(b\\) \\\\)");
+        assert_eq!(pdf_string("Jörg 李"), "(J\\366rg ?)");
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::history::{Date, HistoryError, SessionRecord, Storage};
+use crate::tt::{TtError, TtMatch};
+
+/// Schema changes in the order they were made, the database's
+/// `user_version` counts the ones already applied. Only ever append here.
+const MIGRATIONS: [&str; 2] = [
+    "CREATE TABLE players (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL
+    );
+    CREATE TABLE session_players (
+        session_id INTEGER NOT NULL REFERENCES sessions (id),
+        position INTEGER NOT NULL,
+        player_id INTEGER NOT NULL REFERENCES players (id),
+        PRIMARY KEY (session_id, position)
+    );
+    CREATE TABLE matches (
+        session_id INTEGER NOT NULL REFERENCES sessions (id),
+        position INTEGER NOT NULL,
+        left_player INTEGER NOT NULL,
+        right_player INTEGER NOT NULL,
+        winner INTEGER,
+        PRIMARY KEY (session_id, position)
+    );",
+    "CREATE INDEX sessions_by_date ON sessions (date);",
+];
+
+/// The history in an SQLite database, for when a JSON file of thousands
+/// of games gets unwieldy. Players are numbered per session as in its
+/// playthrough, and a player's position in `session_players` is that
+/// number.
+#[derive(Debug)]
+pub struct SqliteHistory {
+    connection: Connection,
+}
+
+impl SqliteHistory {
+    /// creates the database if needed and brings its schema up to date
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HistoryError> {
+        Self::migrated(Connection::open(path).map_err(database)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, HistoryError> {
+        Self::migrated(Connection::open_in_memory().map_err(database)?)
+    }
+
+    fn migrated(mut connection: Connection) -> Result<Self, HistoryError> {
+        migrate(&mut connection).map_err(database)?;
+
+        let version = schema_version(&connection)?;
+        if version > MIGRATIONS.len() {
+            return Err(HistoryError::Invalid(format!(
+                "history database version {version} is newer than {}",
+                MIGRATIONS.len()
+            )));
+        }
+
+        Ok(Self { connection })
+    }
+
+    fn matches(
+        &self,
+        session_id: i64,
+    ) -> rusqlite::Result<(Vec<TtMatch>, Vec<Option<usize>>)> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT left_player, right_player, winner FROM matches
+             WHERE session_id = ?1 ORDER BY position",
+        )?;
+
+        let rows = statement.query_map([session_id], |row| {
+            Ok((
+                TtMatch::new(row.get(0)?, row.get(1)?),
+                row.get::<_, Option<usize>>(2)?,
+            ))
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map(|rows| rows.into_iter().unzip())
+    }
+
+    fn names(&self, session_id: i64) -> rusqlite::Result<Vec<String>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT players.name FROM session_players
+             JOIN players ON players.id = session_players.player_id
+             WHERE session_id = ?1 ORDER BY position",
+        )?;
+
+        let rows = statement.query_map([session_id], |row| row.get(0))?;
+
+        rows.collect()
+    }
+}
+
+fn database(error: rusqlite::Error) -> HistoryError {
+    HistoryError::Database(Box::new(error))
+}
+
+fn schema_version(connection: &Connection) -> Result<usize, HistoryError> {
+    connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(database)
+}
+
+/// applies the missing migrations, each in its own transaction
+fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+    let applied: usize =
+        connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", version + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+/// `from` and `to` as query parameters, `NULL` if open
+fn range(from: Option<Date>, to: Option<Date>) -> [Option<String>; 2] {
+    [from.map(String::from), to.map(String::from)]
+}
+
+impl Storage for SqliteHistory {
+    fn sessions(
+        &self,
+        from: Option<Date>,
+        to: Option<Date>,
+    ) -> Result<Vec<SessionRecord>, HistoryError> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT id, date FROM sessions
+                 WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date <= ?2)
+                 ORDER BY date, id",
+            )
+            .map_err(database)?;
+
+        let rows = statement
+            .query_map(range(from, to), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(database)?;
+
+        let mut sessions = vec![];
+        for row in rows {
+            let (id, date) = row.map_err(database)?;
+            let (matches, winners) = self.matches(id).map_err(database)?;
+
+            let session = SessionRecord {
+                date: date.parse().map_err(|error: TtError| {
+                    HistoryError::Invalid(error.to_string())
+                })?,
+                names: self.names(id).map_err(database)?,
+                winners: if winners.iter().all(Option::is_none) {
+                    vec![]
+                } else {
+                    winners
+                },
+                matches,
+            };
+            // rows written by hand or by another program
+            session
+                .check()
+                .map_err(|error| HistoryError::Database(Box::new(error)))?;
+
+            sessions.push(session);
+        }
+
+        Ok(sessions)
+    }
+
+    fn add_session(
+        &mut self,
+        session: SessionRecord,
+    ) -> Result<(), HistoryError> {
+        let add = |connection: &mut Connection| -> rusqlite::Result<()> {
+            let transaction = connection.transaction()?;
+
+            transaction.execute(
+                "INSERT INTO sessions (date) VALUES (?1)",
+                [session.date.to_string()],
+            )?;
+            let session_id = transaction.last_insert_rowid();
+
+            for (position, name) in session.names.iter().enumerate() {
+                let existing: Option<i64> = transaction
+                    .query_row(
+                        "SELECT id FROM players WHERE name = ?1",
+                        [name],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let player_id = match existing {
+                    Some(id) => id,
+                    None => {
+                        transaction.execute(
+                            "INSERT INTO players (name) VALUES (?1)",
+                            [name],
+                        )?;
+                        transaction.last_insert_rowid()
+                    }
+                };
+
+                transaction.execute(
+                    "INSERT INTO session_players
+                     (session_id, position, player_id) VALUES (?1, ?2, ?3)",
+                    params![session_id, position, player_id],
+                )?;
+            }
+
+            for (position, m) in session.matches.iter().enumerate() {
+                let winner = session.winners.get(position).copied().flatten();
+
+                transaction.execute(
+                    "INSERT INTO matches (session_id, position, left_player,
+                     right_player, winner) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![session_id, position, m.left(), m.right(), winner],
+                )?;
+            }
+
+            transaction.commit()
+        };
+
+        add(&mut self.connection).map_err(database)
+    }
+
+    /// summed up by the database instead of loading every session
+    fn side_totals(
+        &self,
+        from: Option<Date>,
+        to: Option<Date>,
+    ) -> Result<BTreeMap<String, (usize, usize)>, HistoryError> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT players.name, SUM(sides.left), SUM(sides.right)
+                 FROM (
+                     SELECT session_id, left_player AS position,
+                         1 AS left, 0 AS right FROM matches
+                     UNION ALL
+                     SELECT session_id, right_player, 0, 1 FROM matches
+                 ) AS sides
+                 JOIN sessions ON sessions.id = sides.session_id
+                 JOIN session_players
+                     ON session_players.session_id = sides.session_id
+                     AND session_players.position = sides.position
+                 JOIN players ON players.id = session_players.player_id
+                 WHERE (?1 IS NULL OR sessions.date >= ?1)
+                     AND (?2 IS NULL OR sessions.date <= ?2)
+                 GROUP BY players.name",
+            )
+            .map_err(database)?;
+
+        let rows = statement
+            .query_map(range(from, to), |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+            })
+            .map_err(database)?;
+
+        rows.collect::<Result<_, _>>().map_err(database)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::JsonHistory;
+    use crate::tt::TtPlaythrough;
+
+    fn session(
+        date: &str,
+        names: &[&str],
+        matches: &[(usize, usize)],
+    ) -> SessionRecord {
+        let players = (0..names.len()).collect();
+        let mut playthrough = TtPlaythrough::new(players, 2).unwrap();
+        for players in matches {
+            playthrough.force_match(*players).unwrap();
+        }
+
+        let names = names.iter().map(|name| name.to_string()).collect();
+        SessionRecord::new(date.parse().unwrap(), names, &playthrough).unwrap()
+    }
+
+    #[test]
+    fn test_sessions_and_side_totals() {
+        let mut history = SqliteHistory::open_in_memory().unwrap();
+        let json_path = std::env::temp_dir()
+            .join(format!("tttable-sqlite-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&json_path);
+        let mut json_history = JsonHistory::open(&json_path).unwrap();
+
+        let sessions = [
+            session("2024-03-11", &["Bob", "Alice"], &[(0, 1)])
+                .with_winners(vec![Some(1)])
+                .unwrap(),
+            session(
+                "2024-03-04",
+                &["Alice", "Bob", "Carol"],
+                &[(0, 1), (2, 0)],
+            ),
+        ];
+        for session in sessions.iter() {
+            history.add_session(session.clone()).unwrap();
+            json_history.add_session(session.clone()).unwrap();
+        }
+
+        let loaded = history.sessions(None, None).unwrap();
+        assert_eq!(loaded, [sessions[1].clone(), sessions[0].clone()]);
+
+        for (from, to) in [
+            (None, None),
+            (Some("2024-03-05"), None),
+            (None, Some("2024-03-04")),
+        ] {
+            let from = from.map(|d: &str| d.parse().unwrap());
+            let to = to.map(|d: &str| d.parse().unwrap());
+            assert_eq!(
+                history.side_totals(from, to).unwrap(),
+                json_history.side_totals(from, to).unwrap()
+            );
+        }
+        assert_eq!(history.side_totals(None, None).unwrap()["Alice"], (1, 2));
+
+        std::fs::remove_file(&json_path).unwrap();
+    }
+
+    #[test]
+    fn test_sessions_rejects_invalid_rows() {
+        let mut history = SqliteHistory::open_in_memory().unwrap();
+        history
+            .add_session(session("2024-03-04", &["Alice", "Bob"], &[(0, 1)]))
+            .unwrap();
+
+        for (update, error) in [
+            (
+                "UPDATE matches SET right_player = 2",
+                "player 2 has no name",
+            ),
+            (
+                "UPDATE matches SET right_player = 0",
+                "player 0 plays alone",
+            ),
+        ] {
+            history.connection.execute(update, []).unwrap();
+            assert_eq!(
+                history.sessions(None, None).unwrap_err().to_string(),
+                format!(
+                    "history database error: invalid session of 2024-03-04: \
+                     {error}"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_migrations() {
+        let path = std::env::temp_dir()
+            .join(format!("tttable-migrations-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // a database from before the date index
+        let mut connection = Connection::open(&path).unwrap();
+        let transaction = connection.transaction().unwrap();
+        transaction.execute_batch(MIGRATIONS[0]).unwrap();
+        transaction.pragma_update(None, "user_version", 1).unwrap();
+        transaction.commit().unwrap();
+        drop(connection);
+
+        let history = SqliteHistory::open(&path).unwrap();
+        assert_eq!(schema_version(&history.connection).unwrap(), 2);
+        history
+            .connection
+            .pragma_update(None, "user_version", 3)
+            .unwrap();
+        drop(history);
+
+        assert!(matches!(
+            SqliteHistory::open(&path),
+            Err(HistoryError::Invalid(_))
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::tt::{TtMatch, TtPlaythrough};
+
+/// A round of parallel matches, every player plays at most once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwissRound {
+    pub matches: Vec<TtMatch>,
+    /// player sitting the round out with an odd number of players, counted
+    /// as a win
+    pub bye: Option<usize>,
+    /// winner of every match in `matches`, `None` while it's not played
+    pub winners: Vec<Option<usize>>,
+}
+
+impl SwissRound {
+    pub fn is_finished(&self) -> bool {
+        self.winners.iter().all(Option::is_some)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub player: usize,
+    /// wins and byes
+    pub score: usize,
+    /// scores of the player's opponents added up, the first tie-break
+    pub buchholz: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+/// Swiss-system tournament over the playthrough's players: every round pairs
+/// players with similar scores who didn't play each other yet, the sides are
+/// assigned like colours in chess so that everyone plays about as often on
+/// the left as on the right.
+#[derive(Debug, Clone)]
+pub struct Swiss {
+    playthrough: TtPlaythrough,
+    rounds: Vec<SwissRound>,
+}
+
+impl Swiss {
+    /// matches already in the playthrough count for the side balance and as
+    /// rematches
+    pub fn new(playthrough: TtPlaythrough) -> Self {
+        Self {
+            playthrough,
+            rounds: vec![],
+        }
+    }
+
+    pub fn rounds(&self) -> &[SwissRound] {
+        &self.rounds
+    }
+
+    /// Pairs the next round and returns it, `None` while the current round
+    /// isn't finished.
+    pub fn pair_next_round(&mut self) -> Option<&SwissRound> {
+        if !self.rounds.last().is_none_or(SwissRound::is_finished) {
+            return None;
+        }
+
+        let mut ranked = self
+            .standings()
+            .into_iter()
+            .map(|standing| standing.player)
+            .collect_vec();
+
+        let bye = (ranked.len() % 2 == 1).then(|| self.pick_bye(&ranked));
+        ranked.retain(|player| Some(*player) != bye);
+
+        let pairs = self
+            .pair(&ranked, false)
+            .or_else(|| {
+                log::debug!("Round {} needs rematches", self.rounds.len() + 1);
+                self.pair(&ranked, true)
+            })
+            .expect("rematches allowed, every pairing is possible");
+
+        let matches = pairs
+            .into_iter()
+            .enumerate()
+            .map(|(board, players)| self.assign_sides(players, board))
+            .collect_vec();
+
+        self.rounds.push(SwissRound {
+            winners: vec![None; matches.len()],
+            matches,
+            bye,
+        });
+
+        self.rounds.last()
+    }
+
+    /// Records the winner of their match in the current round, which only
+    /// then counts as played. Returns `false` if `winner` has no unfinished
+    /// match in it.
+    pub fn record_result(&mut self, winner: usize) -> bool {
+        let Some(round) = self.rounds.last_mut() else {
+            return false;
+        };
+
+        let position = round
+            .matches
+            .iter()
+            .zip(round.winners.iter())
+            .position(|(m, w)| m.has_player(winner) && w.is_none());
+
+        match position {
+            Some(position) => {
+                round.winners[position] = Some(winner);
+
+                let tt_match = &round.matches[position];
+                self.playthrough
+                    .force_match((tt_match.left(), tt_match.right()))
+                    .expect("the pairings are of the playthrough's players");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the players ranked by score, then Buchholz, then their number
+    pub fn standings(&self) -> Vec<Standing> {
+        let scores = self.scores();
+        let side_counts = self.playthrough.side_counts();
+
+        self.playthrough
+            .players()
+            .iter()
+            .map(|player| {
+                let buchholz = self
+                    .opponents(*player)
+                    .into_iter()
+                    .map(|opponent| scores[&opponent])
+                    .sum();
+                let (left, right) = side_counts[player];
+
+                Standing {
+                    player: *player,
+                    score: scores[player],
+                    buchholz,
+                    left,
+                    right,
+                }
+            })
+            .sorted_by_key(|s| {
+                (Reverse(s.score), Reverse(s.buchholz), s.player)
+            })
+            .collect_vec()
+    }
+
+    fn scores(&self) -> BTreeMap<usize, usize> {
+        let mut scores: BTreeMap<usize, usize> =
+            self.playthrough.players().iter().map(|p| (*p, 0)).collect();
+
+        for round in self.rounds.iter() {
+            for winner in round.winners.iter().flatten().chain(&round.bye) {
+                *scores.entry(*winner).or_default() += 1;
+            }
+        }
+
+        scores
+    }
+
+    /// the opponents of the matches played so far
+    fn opponents(&self, player: usize) -> Vec<usize> {
+        self.rounds
+            .iter()
+            .flat_map(|round| round.matches.iter().zip(round.winners.iter()))
+            .filter(|(m, winner)| winner.is_some() && m.has_player(player))
+            .map(|(m, _)| m)
+            .map(|m| {
+                if m.left() == player {
+                    m.right()
+                } else {
+                    m.left()
+                }
+            })
+            .collect_vec()
+    }
+
+    /// the lowest ranked player who didn't have a bye yet
+    fn pick_bye(&self, ranked: &[usize]) -> usize {
+        let had_bye = |player: &usize| {
+            self.rounds.iter().any(|round| round.bye == Some(*player))
+        };
+
+        ranked
+            .iter()
+            .rev()
+            .find(|player| !had_bye(player))
+            .or(ranked.last())
+            .copied()
+            .expect("an odd number of players isn't zero")
+    }
+
+    /// pairs the ranked players top down, everyone with the closest ranked
+    /// opponent of the same score who isn't due on the same side, else the
+    /// closest ranked opponent possible
+    fn pair(
+        &self,
+        ranked: &[usize],
+        allow_rematches: bool,
+    ) -> Option<Vec<(usize, usize)>> {
+        let Some((first, rest)) = ranked.split_first() else {
+            return Some(vec![]);
+        };
+
+        let scores = self.scores();
+        let options =
+            rest.iter().enumerate().sorted_by_key(|(index, opponent)| {
+                let same_score = scores[first] == scores[opponent];
+                (!same_score, self.side_conflict(*first, **opponent), *index)
+            });
+
+        for (index, opponent) in options {
+            if !allow_rematches
+                && self.playthrough.pairing_count((*first, *opponent)) > 0
+            {
+                continue;
+            }
+
+            let mut remaining = rest.to_vec();
+            remaining.remove(index);
+
+            if let Some(mut pairs) = self.pair(&remaining, allow_rematches) {
+                pairs.insert(0, (*first, *opponent));
+                return Some(pairs);
+            }
+        }
+
+        None
+    }
+
+    /// Left goes to who played right more often, then to who played right
+    /// last. If both are even the higher ranked `a` plays left on every
+    /// other board, like the alternating colours of a chess Swiss.
+    fn assign_sides(&self, (a, b): (usize, usize), board: usize) -> TtMatch {
+        let played_right_last = |player: usize| {
+            self.playthrough
+                .matches()
+                .iter()
+                .rev()
+                .find(|m| m.has_player(player))
+                .map(|m| m.right() == player)
+        };
+
+        let a_left = match self.side_balance(a).cmp(&self.side_balance(b)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => {
+                match (played_right_last(a), played_right_last(b)) {
+                    (Some(a_right), Some(b_right)) if a_right != b_right => {
+                        a_right
+                    }
+                    _ => board.is_multiple_of(2),
+                }
+            }
+        };
+
+        if a_left {
+            TtMatch::new(a, b)
+        } else {
+            TtMatch::new(b, a)
+        }
+    }
+
+    /// right games minus left games
+    fn side_balance(&self, player: usize) -> i64 {
+        let (left, right) = self.playthrough.side_counts()[&player];
+        right as i64 - left as i64
+    }
+
+    /// whether both players are due on the same side
+    fn side_conflict(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.side_balance(a), self.side_balance(b));
+        a * b > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_rounds(swiss: &mut Swiss, rounds: usize) {
+        for _ in 0..rounds {
+            let round = swiss.pair_next_round().unwrap().clone();
+
+            // the lower numbered player always wins
+            for tt_match in round.matches {
+                swiss.record_result(tt_match.left().min(tt_match.right()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_swiss_avoids_rematches_and_balances_sides() {
+        let players = (0..8).collect_vec();
+        let mut swiss = Swiss::new(TtPlaythrough::new(players, 2).unwrap());
+
+        play_rounds(&mut swiss, 3);
+
+        let pairings = swiss
+            .rounds()
+            .iter()
+            .flat_map(|round| round.matches.iter())
+            .map(|m| (m.left().min(m.right()), m.left().max(m.right())))
+            .collect_vec();
+        assert_eq!(pairings.len(), 12);
+        assert!(pairings.iter().all_unique());
+
+        for standing in swiss.standings() {
+            assert_eq!(standing.left + standing.right, 3);
+            assert!(standing.left.abs_diff(standing.right) <= 1);
+        }
+    }
+
+    #[test]
+    fn test_swiss_pairs_similar_scores() {
+        let players = (0..8).collect_vec();
+        let mut swiss = Swiss::new(TtPlaythrough::new(players, 2).unwrap());
+
+        play_rounds(&mut swiss, 1);
+        let round = swiss.pair_next_round().unwrap().clone();
+
+        // the winners of the first round play each other
+        let scores: BTreeMap<usize, usize> = swiss
+            .standings()
+            .into_iter()
+            .map(|standing| (standing.player, standing.score))
+            .collect();
+        for tt_match in round.matches {
+            assert_eq!(scores[&tt_match.left()], scores[&tt_match.right()]);
+        }
+    }
+
+    #[test]
+    fn test_swiss_bye_and_unfinished_round() {
+        let players = (0..5).collect_vec();
+        let mut swiss = Swiss::new(TtPlaythrough::new(players, 2).unwrap());
+
+        let round = swiss.pair_next_round().unwrap().clone();
+        assert_eq!(round.matches.len(), 2);
+        assert_eq!(round.bye, Some(4));
+        assert_eq!(swiss.pair_next_round(), None);
+
+        // matches without a result don't count yet
+        assert!(!swiss.record_result(4));
+        assert!(swiss.record_result(round.matches[0].left()));
+        let unplayed = round.matches[1].left();
+        let standing =
+            swiss.standings().into_iter().find(|s| s.player == unplayed);
+        assert_eq!(standing.map(|s| (s.left, s.right)), Some((0, 0)));
+
+        assert!(swiss.record_result(round.matches[1].left()));
+
+        let next = swiss.pair_next_round().unwrap();
+        assert_ne!(next.bye, Some(4));
+        assert_eq!(swiss.standings()[0].score, 1);
+    }
+}
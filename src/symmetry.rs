@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+
+use crate::tt::{TtMatch, TtPlaythrough};
+
+/// A renaming of the players, optionally combined with swapping the sides of
+/// the table. The rules don't care about either, so a schedule and its image
+/// are the same schedule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symmetry {
+    mapping: BTreeMap<usize, usize>,
+    mirrored: bool,
+}
+
+impl Symmetry {
+    pub fn mapping(&self) -> &BTreeMap<usize, usize> {
+        &self.mapping
+    }
+
+    pub fn apply(&self, tt_match: &TtMatch) -> TtMatch {
+        let relabeled = tt_match.relabeled(&self.mapping);
+
+        if self.mirrored {
+            relabeled.mirrored()
+        } else {
+            relabeled
+        }
+    }
+
+    pub fn apply_all(&self, matches: &[TtMatch]) -> Vec<TtMatch> {
+        matches.iter().map(|m| self.apply(m)).collect_vec()
+    }
+}
+
+/// all player permutations, each one also mirrored if `mirror` is set
+pub fn symmetries(players: &[usize], mirror: bool) -> Vec<Symmetry> {
+    let mirror_options = if mirror {
+        vec![false, true]
+    } else {
+        vec![false]
+    };
+
+    players
+        .iter()
+        .copied()
+        .permutations(players.len())
+        .cartesian_product(mirror_options)
+        .map(|(permutation, mirrored)| Symmetry {
+            mapping: players.iter().copied().zip(permutation).collect(),
+            mirrored,
+        })
+        .collect_vec()
+}
+
+/// only the symmetries under which the playthrough's rules stay the same,
+/// e.g. two players with different games limits can't be swapped
+pub fn respecting_rules(
+    symmetries: Vec<Symmetry>,
+    playthrough: &TtPlaythrough,
+) -> Vec<Symmetry> {
+    symmetries
+        .into_iter()
+        .filter(|s| {
+            s.mapping.iter().all(|(from, to)| {
+                playthrough.games_limit(*from) == playthrough.games_limit(*to)
+            })
+        })
+        .collect_vec()
+}
+
+/// only the symmetries which leave `history` unchanged, continuations of the
+/// history are only equivalent under those
+pub fn stabilizing(
+    symmetries: Vec<Symmetry>,
+    history: &[TtMatch],
+) -> Vec<Symmetry> {
+    symmetries
+        .into_iter()
+        .filter(|s| s.apply_all(history) == history)
+        .collect_vec()
+}
+
+/// the smallest image of the sequence, equal for all equivalent sequences
+pub fn canonicalize(
+    matches: &[TtMatch],
+    symmetries: &[Symmetry],
+) -> Vec<TtMatch> {
+    symmetries
+        .iter()
+        .map(|s| s.apply_all(matches))
+        .min()
+        .unwrap_or_else(|| matches.to_vec())
+}
+
+pub fn is_canonical(matches: &[TtMatch], symmetries: &[Symmetry]) -> bool {
+    symmetries
+        .iter()
+        .all(|s| s.apply_all(matches).as_slice() >= matches)
+}
+
+/// number of different sequences equivalent to this one
+pub fn orbit_size(matches: &[TtMatch], symmetries: &[Symmetry]) -> usize {
+    symmetries
+        .iter()
+        .map(|s| s.apply_all(matches))
+        .collect::<BTreeSet<_>>()
+        .len()
+        .max(1)
+}
+
+/// keeps the canonical form of every class of equivalent sequences once
+pub fn deduplicate(
+    sequences: &[Vec<TtMatch>],
+    symmetries: &[Symmetry],
+) -> Vec<Vec<TtMatch>> {
+    sequences
+        .iter()
+        .map(|sequence| canonicalize(sequence, symmetries))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::GamesLimit;
+
+    const TEST_PLAYERS: [usize; 3] = [0, 1, 2];
+
+    fn sequence(matches: &[(usize, usize)]) -> Vec<TtMatch> {
+        matches.iter().map(|(l, r)| TtMatch::new(*l, *r)).collect()
+    }
+
+    #[test]
+    fn test_symmetries() {
+        assert_eq!(symmetries(&TEST_PLAYERS, false).len(), 6);
+        assert_eq!(symmetries(&TEST_PLAYERS, true).len(), 12);
+    }
+
+    #[test]
+    fn test_canonicalize_relabeling() {
+        let all = symmetries(&TEST_PLAYERS, false);
+
+        let a = sequence(&[(2, 1), (0, 2)]);
+        let b = sequence(&[(0, 2), (1, 0)]);
+
+        assert_eq!(canonicalize(&a, &all), sequence(&[(0, 1), (2, 0)]));
+        assert_eq!(canonicalize(&a, &all), canonicalize(&b, &all));
+        assert!(is_canonical(&canonicalize(&a, &all), &all));
+        assert!(!is_canonical(&a, &all));
+    }
+
+    #[test]
+    fn test_canonicalize_mirroring() {
+        let relabel_only = symmetries(&TEST_PLAYERS, false);
+        let with_mirror = symmetries(&TEST_PLAYERS, true);
+
+        // 0-1 1-2 can't be relabeled into 1-0 2-1 without the mirror
+        let a = sequence(&[(0, 1), (1, 2)]);
+        let b = sequence(&[(1, 0), (2, 1)]);
+
+        assert_ne!(
+            canonicalize(&a, &relabel_only),
+            canonicalize(&b, &relabel_only)
+        );
+        assert_eq!(
+            canonicalize(&a, &with_mirror),
+            canonicalize(&b, &with_mirror)
+        );
+    }
+
+    #[test]
+    fn test_orbit_size_and_deduplicate() {
+        let all = symmetries(&TEST_PLAYERS, false);
+
+        assert_eq!(orbit_size(&sequence(&[(0, 1)]), &all), 6);
+        assert_eq!(orbit_size(&sequence(&[(0, 1), (2, 0)]), &all), 6);
+
+        let sequences = vec![
+            sequence(&[(0, 1), (2, 0)]),
+            sequence(&[(2, 1), (0, 2)]),
+            sequence(&[(0, 1), (1, 2)]),
+        ];
+        assert_eq!(deduplicate(&sequences, &all).len(), 2);
+    }
+
+    #[test]
+    fn test_stabilizing() {
+        let history = sequence(&[(0, 1)]);
+
+        // only the identity keeps 0-1 as it is
+        let stable = stabilizing(symmetries(&TEST_PLAYERS, false), &history);
+        assert_eq!(stable.len(), 1);
+
+        // swapping 0 and 1 together with the sides keeps it too
+        let stable = stabilizing(symmetries(&TEST_PLAYERS, true), &history);
+        assert_eq!(stable.len(), 2);
+    }
+
+    #[test]
+    fn test_respecting_rules() {
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .with_games_limit(0, GamesLimit::consecutive(1))
+            .unwrap();
+
+        // 0 has to stay 0, only 1 and 2 can be swapped
+        let respecting =
+            respecting_rules(symmetries(&TEST_PLAYERS, false), &playthrough);
+        assert_eq!(respecting.len(), 2);
+        assert!(respecting.iter().all(|s| s.mapping()[&0] == 0));
+    }
+}
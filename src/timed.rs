@@ -0,0 +1,576 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::recommend::RecommendWeights;
+use crate::tt::{Constraint, Rule, TtError, TtMatch, TtPlaythrough};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// A time of day in minutes after midnight, written as `HH:MM`. Times
+/// after the next midnight, e.g. from `plus`, serialize as `HH:MM+1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    pub fn new(hours: u32, minutes: u32) -> Option<Self> {
+        (hours < 24 && minutes < 60).then_some(Self(hours * 60 + minutes))
+    }
+
+    pub fn minutes_after_midnight(&self) -> u32 {
+        self.0
+    }
+
+    /// `minutes` later, past midnight if need be
+    pub fn plus(&self, minutes: u32) -> Self {
+        Self(self.0 + minutes)
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60 % 24, self.0 % 60)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = TtError;
+
+    fn from_str(text: &str) -> Result<Self, TtError> {
+        text.split_once(':')
+            .and_then(|(hours, minutes)| {
+                TimeOfDay::new(hours.parse().ok()?, minutes.parse().ok()?)
+            })
+            .ok_or_else(|| {
+                TtError::InvalidConfiguration(format!(
+                    "{text} is no HH:MM time"
+                ))
+            })
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        match time.0 / MINUTES_PER_DAY {
+            0 => time.to_string(),
+            days => format!("{time}+{days}"),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = TtError;
+
+    fn try_from(text: String) -> Result<Self, TtError> {
+        let Some((time, days)) = text.split_once('+') else {
+            return text.parse();
+        };
+        let time: TimeOfDay = time.parse()?;
+        let days: u32 = days.parse().map_err(|_| {
+            TtError::InvalidConfiguration(format!(
+                "{text} is no HH:MM+DAYS time"
+            ))
+        })?;
+
+        Ok(time.plus(days * MINUTES_PER_DAY))
+    }
+}
+
+/// A calendar day, written as `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+
+        (1..=days_in_month)
+            .contains(&day)
+            .then_some(Self { year, month, day })
+    }
+
+    /// the current day in UTC
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("the clock is after 1970")
+            .as_secs();
+
+        Self::from_days_since_epoch((seconds / 86_400) as i64)
+    }
+
+    /// `days` later
+    pub fn plus_days(&self, days: u32) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + i64::from(days))
+    }
+
+    /// the inverse of `from_days_since_epoch`, see
+    /// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    fn days_since_epoch(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let shifted_month = (i64::from(self.month) + 9) % 12;
+        let day_of_year =
+            (153 * shifted_month + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4
+            - year_of_era / 100
+            + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// the civil date of a day count, see
+    /// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    pub(crate) fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460
+            + day_of_era / 36_524
+            - day_of_era / 146_096)
+            / 365;
+        let day_of_year = day_of_era
+            - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = TtError;
+
+    fn from_str(text: &str) -> Result<Self, TtError> {
+        let invalid = || {
+            TtError::InvalidConfiguration(format!(
+                "{text} is no YYYY-MM-DD date"
+            ))
+        };
+
+        let mut parts = text.splitn(3, '-');
+        let mut next = || parts.next().and_then(|part| part.parse().ok());
+        let (year, month, day) = (next(), next(), next());
+
+        Date::new(
+            year.ok_or_else(invalid)? as i32,
+            month.ok_or_else(invalid)?,
+            day.ok_or_else(invalid)?,
+        )
+        .ok_or_else(invalid)
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = TtError;
+
+    fn try_from(text: String) -> Result<Self, TtError> {
+        text.parse()
+    }
+}
+
+/// When a player is at the table, from arriving until leaving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Availability {
+    pub arrive: TimeOfDay,
+    pub leave: TimeOfDay,
+}
+
+impl Availability {
+    fn covers(&self, start: TimeOfDay, end: TimeOfDay) -> bool {
+        self.arrive <= start && end <= self.leave
+    }
+}
+
+/// A match of a timed schedule with its estimated start and end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedMatch {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    pub tt_match: TtMatch,
+}
+
+impl fmt::Display for TimedMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{} {}", self.start, self.end, self.tt_match)
+    }
+}
+
+/// Plans matches of a fixed duration one after another, each between
+/// players who are present for the whole match, the top recommendation
+/// among those first. Players without an availability window are there all
+/// the time.
+#[derive(Debug, Clone)]
+pub struct TimedSchedule {
+    playthrough: TtPlaythrough,
+    match_minutes: u32,
+    availability: BTreeMap<usize, Availability>,
+    matches: Vec<TimedMatch>,
+    pub weights: RecommendWeights,
+}
+
+impl TimedSchedule {
+    /// Rule 5 can't be a hard rule here as the players who aren't there
+    /// yet or anymore would count as resting.
+    pub fn new(
+        playthrough: TtPlaythrough,
+        match_minutes: u32,
+    ) -> Result<Self, TtError> {
+        if match_minutes == 0 {
+            return Err(TtError::InvalidConfiguration(
+                "matches need to take at least a minute".into(),
+            ));
+        }
+        if playthrough.constraint(Rule::MaxRest) == Some(Constraint::Hard) {
+            return Err(TtError::InvalidConfiguration(
+                "rule 5 can't be hard with availability windows".into(),
+            ));
+        }
+
+        Ok(Self {
+            playthrough,
+            match_minutes,
+            availability: BTreeMap::new(),
+            matches: vec![],
+            weights: RecommendWeights::default(),
+        })
+    }
+
+    /// a window may end on the next day if the evening does, e.g. `23:00` to
+    /// `00:30` for an evening until `01:00`, see `plan`
+    pub fn with_availability(
+        mut self,
+        player: usize,
+        availability: Availability,
+    ) -> Result<Self, TtError> {
+        if !self.playthrough.players().contains(&player) {
+            return Err(TtError::UnknownPlayer(player));
+        }
+        if availability.leave == availability.arrive {
+            return Err(TtError::InvalidConfiguration(format!(
+                "player {player} leaves at {} when arriving",
+                availability.leave
+            )));
+        }
+
+        self.availability.insert(player, availability);
+        Ok(self)
+    }
+
+    pub fn playthrough(&self) -> &TtPlaythrough {
+        &self.playthrough
+    }
+
+    pub fn matches(&self) -> &[TimedMatch] {
+        &self.matches
+    }
+
+    fn is_present(
+        &self,
+        windows: &BTreeMap<usize, Availability>,
+        player: usize,
+        start: TimeOfDay,
+    ) -> bool {
+        windows.get(&player).is_none_or(|availability| {
+            availability.covers(start, start.plus(self.match_minutes))
+        })
+    }
+
+    /// Plays matches from `start` on, the last one ending by `end`. An `end`
+    /// not after `start` is on the next day, and so are the times of the
+    /// availability windows up to that `end`, e.g. an arrival at `00:30`
+    /// for an evening from `22:00` to `01:00`. When nobody present can
+    /// play, the schedule waits for the next player to arrive. Fails for a
+    /// window which still leaves before arriving, e.g. `18:00` to `17:00`.
+    pub fn plan(
+        &mut self,
+        start: TimeOfDay,
+        end: TimeOfDay,
+    ) -> Result<&[TimedMatch], TtError> {
+        let past_midnight = end <= start;
+        let on_session_day = |time: TimeOfDay| {
+            if past_midnight && time <= end {
+                time.plus(MINUTES_PER_DAY)
+            } else {
+                time
+            }
+        };
+
+        let mut windows = BTreeMap::new();
+        for (player, availability) in self.availability.iter() {
+            let arrive = on_session_day(availability.arrive);
+            let leave = on_session_day(availability.leave);
+            if leave < arrive {
+                return Err(TtError::InvalidConfiguration(format!(
+                    "player {player} leaves at {} before arriving at {}",
+                    availability.leave, availability.arrive
+                )));
+            }
+
+            windows.insert(*player, Availability { arrive, leave });
+        }
+        let end = on_session_day(end);
+        let mut time = start;
+
+        while time.plus(self.match_minutes) <= end {
+            let next = self
+                .playthrough
+                .recommend_next_matches(&self.weights)
+                .into_iter()
+                .map(|recommendation| recommendation.players)
+                .find(|(left, right)| {
+                    self.is_present(&windows, *left, time)
+                        && self.is_present(&windows, *right, time)
+                });
+
+            let Some(players) = next else {
+                // nothing changes until somebody else arrives
+                match windows
+                    .values()
+                    .map(|availability| availability.arrive)
+                    .filter(|arrive| *arrive > time)
+                    .min()
+                {
+                    Some(arrive) => {
+                        time = arrive;
+                        continue;
+                    }
+                    None => break,
+                }
+            };
+
+            self.playthrough
+                .play_match(players)
+                .expect("recommendations are possible matches");
+            self.matches.push(TimedMatch {
+                start: time,
+                end: time.plus(self.match_minutes),
+                tt_match: TtMatch::new(players.0, players.1),
+            });
+            time = time.plus(self.match_minutes);
+        }
+
+        Ok(&self.matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        text.parse().unwrap()
+    }
+
+    fn time(text: &str) -> TimeOfDay {
+        text.parse().unwrap()
+    }
+
+    fn available(arrive: &str, leave: &str) -> Availability {
+        Availability {
+            arrive: time(arrive),
+            leave: time(leave),
+        }
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date("2024-02-29").to_string(), "2024-02-29");
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-1-x".parse::<Date>().is_err());
+        assert!(date("2024-01-31") < date("2024-02-01"));
+
+        assert_eq!(Date::from_days_since_epoch(0), date("1970-01-01"));
+        assert_eq!(Date::from_days_since_epoch(19_782), date("2024-02-29"));
+        assert_eq!(date("2024-02-29").days_since_epoch(), 19_782);
+        assert_eq!(date("2023-12-31").plus_days(60), date("2024-02-29"));
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        assert_eq!(time("18:05").minutes_after_midnight(), 18 * 60 + 5);
+        assert_eq!(time("23:50").plus(20).to_string(), "00:10");
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("18".parse::<TimeOfDay>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_time_of_day_serde_after_midnight() {
+        let after_midnight = time("23:50").plus(20);
+
+        let json = serde_json::to_string(&after_midnight).unwrap();
+        assert_eq!(json, "\"00:10+1\"");
+        let read: TimeOfDay = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, after_midnight);
+        assert_eq!(serde_json::to_string(&time("23:50")).unwrap(), "\"23:50\"");
+        assert!(serde_json::from_str::<TimeOfDay>("\"00:10+x\"").is_err());
+    }
+
+    #[test]
+    fn test_plan_within_availability() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut schedule = TimedSchedule::new(playthrough, 15)
+            .unwrap()
+            .with_availability(2, available("18:30", "21:00"))
+            .unwrap()
+            .with_availability(3, available("18:00", "19:00"))
+            .unwrap();
+
+        let matches = schedule
+            .plan(time("18:00"), time("20:00"))
+            .unwrap()
+            .to_vec();
+
+        assert_eq!(matches.len(), 8);
+        assert_eq!(matches[0].start, time("18:00"));
+        assert_eq!(matches.last().unwrap().end, time("20:00"));
+        for m in matches.iter() {
+            assert_eq!(m.end, m.start.plus(15));
+            assert!(m.start >= time("18:30") || !m.tt_match.has_player(2));
+            assert!(m.end <= time("19:00") || !m.tt_match.has_player(3));
+        }
+        assert_eq!(schedule.playthrough().matches().len(), 8);
+    }
+
+    #[test]
+    fn test_plan_waits_for_arrivals() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+        let mut schedule = TimedSchedule::new(playthrough, 20)
+            .unwrap()
+            .with_availability(0, available("19:00", "19:20"))
+            .unwrap()
+            .with_availability(1, available("19:00", "20:00"))
+            .unwrap()
+            .with_availability(2, available("19:30", "20:00"))
+            .unwrap();
+
+        let starts = schedule
+            .plan(time("18:00"), time("22:00"))
+            .unwrap()
+            .iter()
+            .map(|m| m.start.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(starts, ["19:00", "19:30"]);
+    }
+
+    #[test]
+    fn test_new_checks_the_settings() {
+        let playthrough = TtPlaythrough::new(vec![0, 1], 2).unwrap();
+
+        assert!(TimedSchedule::new(playthrough.clone(), 0).is_err());
+        let resting = playthrough.clone().with_max_rest(1).unwrap();
+        assert!(TimedSchedule::new(resting, 10).is_err());
+        let schedule = TimedSchedule::new(playthrough, 10).unwrap();
+        assert!(schedule
+            .clone()
+            .with_availability(5, available("18:00", "19:00"))
+            .is_err());
+        assert!(schedule
+            .with_availability(0, available("19:00", "19:00"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_plan_past_midnight() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut schedule = TimedSchedule::new(playthrough, 30)
+            .unwrap()
+            .with_availability(0, available("21:00", "23:30"))
+            .unwrap()
+            .with_availability(2, available("00:00", "01:00"))
+            .unwrap();
+
+        let matches = schedule
+            .plan(time("22:00"), time("01:00"))
+            .unwrap()
+            .to_vec();
+        let midnight = MINUTES_PER_DAY;
+
+        assert_eq!(matches.last().unwrap().end.to_string(), "01:00");
+        for m in matches.iter() {
+            let start = m.start.minutes_after_midnight();
+            // 0 leaves at 23:30 and 2 only arrives at midnight
+            assert!(start < 23 * 60 + 30 || !m.tt_match.has_player(0));
+            assert!(start >= midnight || !m.tt_match.has_player(2));
+        }
+        assert!(matches.iter().any(|m| m.tt_match.has_player(2)));
+    }
+
+    #[test]
+    fn test_plan_rejects_reversed_windows() {
+        let playthrough = TtPlaythrough::new(vec![0, 1], 2).unwrap();
+        let schedule = TimedSchedule::new(playthrough, 30)
+            .unwrap()
+            .with_availability(0, available("23:00", "00:30"))
+            .unwrap();
+
+        // only the evening until 01:00 runs past midnight
+        assert!(schedule.clone().plan(time("22:00"), time("01:00")).is_ok());
+        assert_eq!(
+            schedule
+                .clone()
+                .plan(time("18:00"), time("23:30"))
+                .unwrap_err(),
+            TtError::InvalidConfiguration(
+                "player 0 leaves at 00:30 before arriving at 23:00".into()
+            )
+        );
+        let mut schedule = schedule
+            .with_availability(1, available("18:00", "17:00"))
+            .unwrap();
+        assert!(schedule.plan(time("17:00"), time("01:00")).is_err());
+    }
+}
@@ -3,7 +3,8 @@ use std::fmt;
 
 use itertools::Itertools;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TtMatch {
     left: usize,
     right: usize,
@@ -14,6 +15,31 @@ impl TtMatch {
         Self { left, right }
     }
 
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    /// the same match with every player replaced according to `mapping`,
+    /// players missing from the mapping keep their number
+    pub fn relabeled(&self, mapping: &BTreeMap<usize, usize>) -> Self {
+        let relabel = |p: usize| *mapping.get(&p).unwrap_or(&p);
+
+        Self::new(relabel(self.left), relabel(self.right))
+    }
+
+    pub fn has_player(&self, player: usize) -> bool {
+        self.left == player || self.right == player
+    }
+
+    /// the same match with left and right swapped
+    pub fn mirrored(&self) -> Self {
+        Self::new(self.right, self.left)
+    }
+
     pub fn check_same_players(&self, players: (usize, usize)) -> bool {
         let players_in_match = [self.left, self.right];
 
@@ -28,9 +54,346 @@ impl fmt::Display for TtMatch {
     }
 }
 
+/// Rule 2 limit of a player: at most `max_games` games within any `window`
+/// consecutive matches, the match about to be played included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GamesLimit {
+    pub max_games: usize,
+    pub window: usize,
+}
+
+impl GamesLimit {
+    /// at most `max_games` games in a row
+    pub fn consecutive(max_games: usize) -> Self {
+        Self {
+            max_games,
+            window: max_games + 1,
+        }
+    }
+
+    /// at most `max_games` games within any `window` consecutive matches
+    pub fn within(max_games: usize, window: usize) -> Self {
+        Self { max_games, window }
+    }
+}
+
+/// at least one game and a window larger than `max_games`, else every game
+/// in it would be allowed
+fn check_games_limit(limit: GamesLimit) -> Result<(), TtError> {
+    if limit.max_games == 0 || limit.window <= limit.max_games {
+        return Err(TtError::InvalidConfiguration(format!(
+            "invalid games limit {limit}: at least 1 game within a larger \
+             window is needed"
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_max_rest(max_rest: usize) -> Result<(), TtError> {
+    if max_rest == 0 {
+        return Err(TtError::InvalidConfiguration(
+            "invalid max rest 0: at least 1 match of rest is needed"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for GamesLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "max {} of {} matches", self.max_games, self.window)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Rule {
+    /// Rule 1: don't play same players
+    SamePlayers,
+    /// Rule 2: don't play three times in a row
+    RepeatedGames,
+    /// Rule 3: don't play on the same side of the table as in the game before
+    SameSide,
+    /// Rule 4: don't play on the same sides when facing the same opponent again
+    SameSidesVsSameOpponent,
+    /// Rule 5: don't let anybody else sit out for too long
+    MaxRest,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::SamePlayers,
+        Rule::RepeatedGames,
+        Rule::SameSide,
+        Rule::SameSidesVsSameOpponent,
+        Rule::MaxRest,
+    ];
+
+    pub fn number(&self) -> usize {
+        match self {
+            Rule::SamePlayers => 1,
+            Rule::RepeatedGames => 2,
+            Rule::SameSide => 3,
+            Rule::SameSidesVsSameOpponent => 4,
+            Rule::MaxRest => 5,
+        }
+    }
+
+    pub fn from_number(number: usize) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.number() == number)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rule {}", self.number())
+    }
+}
+
+/// Why a playthrough couldn't be built or a match couldn't be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TtError {
+    /// the player isn't one of `TtPlaythrough::players`
+    UnknownPlayer(usize),
+    /// a match of a player against themselves
+    SelfMatch(usize),
+    InvalidConfiguration(String),
+    /// the hard rules the match breaks
+    RuleViolation {
+        players: (usize, usize),
+        rules: Vec<Rule>,
+    },
+}
+
+impl fmt::Display for TtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TtError::UnknownPlayer(player) => {
+                write!(f, "player {player} doesn't take part")
+            }
+            TtError::SelfMatch(player) => {
+                write!(f, "player {player} can't play against themselves")
+            }
+            TtError::InvalidConfiguration(message) => {
+                write!(f, "invalid configuration: {message}")
+            }
+            TtError::RuleViolation { players, rules } => write!(
+                f,
+                "match {} - {} breaks {}",
+                players.0,
+                players.1,
+                rules.iter().join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TtError {}
+
+/// Whether breaking a rule forbids a match or only makes it less preferable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Constraint {
+    Hard,
+    /// allowed, but adds the weight to the match's penalty
+    Soft(u32),
+}
+
+/// The rules a playthrough keeps and whether each of them is hard or soft.
+///
+/// Rules 1 to 4 are kept by default, rule 5 once a maximum rest is set with
+/// `TtPlaythrough::with_max_rest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct RuleSet {
+    rules: BTreeMap<Rule, Constraint>,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::empty()
+            .with_rule(Rule::SamePlayers)
+            .with_rule(Rule::RepeatedGames)
+            .with_rule(Rule::SameSide)
+            .with_rule(Rule::SameSidesVsSameOpponent)
+    }
+}
+
+impl RuleSet {
+    /// no rules at all, every match is possible
+    pub fn empty() -> Self {
+        Self {
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// keeps the rule as a hard rule
+    pub fn with_rule(self, rule: Rule) -> Self {
+        self.with_constraint(rule, Constraint::Hard)
+    }
+
+    /// keeps the rule, hard or soft
+    pub fn with_constraint(
+        mut self,
+        rule: Rule,
+        constraint: Constraint,
+    ) -> Self {
+        self.rules.insert(rule, constraint);
+        self
+    }
+
+    pub fn without_rule(mut self, rule: Rule) -> Self {
+        self.rules.remove(&rule);
+        self
+    }
+
+    pub fn contains(&self, rule: Rule) -> bool {
+        self.rules.contains_key(&rule)
+    }
+
+    /// `None` if the rule isn't kept at all
+    pub fn constraint(&self, rule: Rule) -> Option<Constraint> {
+        self.rules.get(&rule).copied()
+    }
+
+    /// the kept rules in the order of their numbers
+    pub fn rules(&self) -> impl Iterator<Item = (Rule, Constraint)> + '_ {
+        self.rules
+            .iter()
+            .map(|(rule, constraint)| (*rule, *constraint))
+    }
+}
+
+/// Everything of a playthrough's history the rules can still look at.
+///
+/// Two playthroughs with the same state allow exactly the same matches from
+/// now on, no matter how long their histories are.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TtState {
+    /// the last matches rules 1 to 3 can still look at, oldest first
+    pub recent: Vec<TtMatch>,
+    /// the latest match of every pairing played so far, sorted
+    pub pairings: Vec<TtMatch>,
+    /// matches every player sat out since their last game, only with rule 5
+    pub rests: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for TtState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.recent.is_empty() {
+            return write!(f, "start");
+        }
+
+        let short = |m: &TtMatch| format!("{}-{}", m.left, m.right);
+
+        write!(f, "{}", self.recent.iter().map(short).join(" "))?;
+
+        if !self.pairings.is_empty() {
+            write!(f, " / {}", self.pairings.iter().map(short).join(" "))?;
+        }
+
+        if !self.rests.is_empty() {
+            let mut rests = self.rests.iter().map(|(p, r)| format!("{p}:{r}"));
+            write!(f, " / rests {}", rests.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How much worse it is to play on each side, in any unit, e.g. points lost
+/// per game. Sides can change their quality during a session, e.g. when the
+/// evening sun starts to glare on the right side, so a session is split into
+/// periods starting after a number of matches, each with its own (left,
+/// right) disadvantage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SideDisadvantage {
+    /// (matches played before, (left, right)), sorted, the first after 0
+    periods: Vec<(usize, (u32, u32))>,
+}
+
+impl SideDisadvantage {
+    /// the disadvantages from the first match on
+    pub fn new(left: u32, right: u32) -> Self {
+        Self {
+            periods: vec![(0, (left, right))],
+        }
+    }
+
+    /// the disadvantages once `played` matches are played, until the next
+    /// period
+    pub fn after_matches(
+        mut self,
+        played: usize,
+        left: u32,
+        right: u32,
+    ) -> Self {
+        let index = self.periods.partition_point(|(p, _)| *p < played);
+        if self.periods.get(index).is_some_and(|(p, _)| *p == played) {
+            self.periods[index].1 = (left, right);
+        } else {
+            self.periods.insert(index, (played, (left, right)));
+        }
+        self
+    }
+
+    /// (left, right) disadvantage of the match after `played` matches
+    pub fn at(&self, played: usize) -> (u32, u32) {
+        let index = self.periods.partition_point(|(p, _)| *p <= played);
+
+        index
+            .checked_sub(1)
+            .and_then(|index| self.periods.get(index))
+            .or(self.periods.first())
+            .map_or((0, 0), |(_, disadvantage)| *disadvantage)
+    }
+
+    /// the periods start with the first match and are in order, as `new`
+    /// and `after_matches` keep them, a loaded one is checked
+    #[cfg(feature = "serde")]
+    fn check(&self) -> Result<(), TtError> {
+        let starts_at_0 = self.periods.first().is_some_and(|(p, _)| *p == 0);
+        let in_order = self
+            .periods
+            .iter()
+            .tuple_windows()
+            .all(|((a, _), (b, _))| a < b);
+
+        if !starts_at_0 || !in_order {
+            return Err(TtError::InvalidConfiguration(
+                "the side disadvantage's periods have to start at match 0 \
+                 and be in order"
+                    .to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "PlaythroughRecord", try_from = "PlaythroughRecord")
+)]
 pub struct TtPlaythrough {
     max_repeting_games_per_player: usize,
+    default_games_limit: GamesLimit,
+    games_limits: BTreeMap<usize, GamesLimit>,
+    max_rest: Option<usize>,
+    rules: RuleSet,
+    /// (left, right) games of earlier sessions, see `with_carried_sides`
+    carried_sides: BTreeMap<usize, (usize, usize)>,
+    /// see `with_side_disadvantage`
+    side_disadvantage: Option<SideDisadvantage>,
     players: Vec<usize>,
     matches: Vec<TtMatch>,
 }
@@ -39,15 +402,138 @@ impl TtPlaythrough {
     //
     // construction and control
     //
+    /// fails if there are less than two players, a player is listed twice
+    /// or `max_repeting_games_per_player` is 0
     pub fn new(
         players: Vec<usize>,
         max_repeting_games_per_player: usize,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, TtError> {
+        let invalid =
+            |message: String| Err(TtError::InvalidConfiguration(message));
+
+        if players.len() < 2 {
+            return invalid("at least 2 players are needed".to_string());
+        }
+        if let Some(player) = players.iter().duplicates().next() {
+            return invalid(format!("player {player} is listed twice"));
+        }
+        if max_repeting_games_per_player == 0 {
+            return invalid(
+                "max_repeting_games_per_player must be at least 1".to_string(),
+            );
+        }
+
+        Ok(Self {
             max_repeting_games_per_player,
+            default_games_limit: GamesLimit::consecutive(
+                max_repeting_games_per_player,
+            ),
+            games_limits: BTreeMap::new(),
+            max_rest: None,
+            rules: RuleSet::default(),
+            carried_sides: BTreeMap::new(),
+            side_disadvantage: None,
             players,
             matches: vec![],
+        })
+    }
+
+    /// replaces the rule 2 limit of every player without an own limit, by
+    /// default `max_repeting_games_per_player` games in a row, fails if the
+    /// limit allows no game or doesn't limit anything
+    pub fn with_default_games_limit(
+        mut self,
+        limit: GamesLimit,
+    ) -> Result<Self, TtError> {
+        check_games_limit(limit)?;
+
+        self.default_games_limit = limit;
+        Ok(self)
+    }
+
+    /// rule 2 limit for a single player, e.g. only one game in a row for
+    /// someone who needs a break after every game
+    pub fn with_games_limit(
+        mut self,
+        player: usize,
+        limit: GamesLimit,
+    ) -> Result<Self, TtError> {
+        self.check_player(player)?;
+        check_games_limit(limit)?;
+
+        self.games_limits.insert(player, limit);
+        Ok(self)
+    }
+
+    /// the player's (left, right) games of earlier sessions, the
+    /// recommendations even them out together with this session's games
+    pub fn with_carried_sides(
+        mut self,
+        player: usize,
+        sides: (usize, usize),
+    ) -> Result<Self, TtError> {
+        self.check_player(player)?;
+
+        self.carried_sides.insert(player, sides);
+        Ok(self)
+    }
+
+    /// the recommendations even out the players' total disadvantage instead
+    /// of their left and right games
+    pub fn with_side_disadvantage(
+        mut self,
+        side_disadvantage: SideDisadvantage,
+    ) -> Self {
+        self.side_disadvantage = Some(side_disadvantage);
+        self
+    }
+
+    /// enables rule 5: nobody sits out more than `max_rest` matches in a
+    /// row, fails for 0
+    pub fn with_max_rest(mut self, max_rest: usize) -> Result<Self, TtError> {
+        check_max_rest(max_rest)?;
+
+        self.max_rest = Some(max_rest);
+        if self.constraint(Rule::MaxRest).is_none() {
+            self.rules = self.rules.with_rule(Rule::MaxRest);
+        }
+        Ok(self)
+    }
+
+    fn check_player(&self, player: usize) -> Result<(), TtError> {
+        if !self.players.contains(&player) {
+            return Err(TtError::UnknownPlayer(player));
         }
+
+        Ok(())
+    }
+
+    /// replaces the kept rules, rules 1 to 4 by default
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// keeps the rule as a hard rule, e.g. rule 4 again after
+    /// `without_rule`
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rules = self.rules.with_rule(rule);
+        self
+    }
+
+    pub fn without_rule(mut self, rule: Rule) -> Self {
+        self.rules = self.rules.without_rule(rule);
+        self
+    }
+
+    /// keeps the rule, hard or soft
+    pub fn with_constraint(
+        mut self,
+        rule: Rule,
+        constraint: Constraint,
+    ) -> Self {
+        self.rules = self.rules.with_constraint(rule, constraint);
+        self
     }
 
     pub fn log_matches_so_far(&self) {
@@ -67,35 +553,325 @@ impl TtPlaythrough {
         self.matches = vec![];
     }
 
+    /// takes back the last match, e.g. one entered by mistake
+    pub fn undo_match(&mut self) -> Option<TtMatch> {
+        self.matches.pop()
+    }
+
+    pub fn players(&self) -> &[usize] {
+        &self.players
+    }
+
+    pub fn matches(&self) -> &[TtMatch] {
+        &self.matches
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// `None` if the playthrough doesn't keep the rule
+    pub fn constraint(&self, rule: Rule) -> Option<Constraint> {
+        self.rules.constraint(rule)
+    }
+
+    pub fn games_limit(&self, player: usize) -> GamesLimit {
+        *self
+            .games_limits
+            .get(&player)
+            .unwrap_or(&self.default_games_limit)
+    }
+
+    /// number of matches every player sat out since their last game, or
+    /// since the start if they didn't play yet
+    pub fn rests(&self) -> BTreeMap<usize, usize> {
+        self.players
+            .iter()
+            .map(|p| {
+                let rest = self
+                    .matches
+                    .iter()
+                    .rev()
+                    .take_while(|m| !m.has_player(*p))
+                    .count();
+
+                (*p, rest)
+            })
+            .collect()
+    }
+
+    /// how often every player played on the (left, right) side so far
+    pub fn side_counts(&self) -> BTreeMap<usize, (usize, usize)> {
+        let mut counts: BTreeMap<usize, (usize, usize)> =
+            self.players.iter().map(|p| (*p, (0, 0))).collect();
+
+        for m in self.matches.iter() {
+            counts.entry(m.left).or_default().0 += 1;
+            counts.entry(m.right).or_default().1 += 1;
+        }
+
+        counts
+    }
+
+    /// `side_counts` plus the sides carried over from earlier sessions
+    pub fn total_side_counts(&self) -> BTreeMap<usize, (usize, usize)> {
+        let mut counts = self.side_counts();
+
+        for (player, (left, right)) in self.carried_sides.iter() {
+            let count = counts.entry(*player).or_default();
+            count.0 += left;
+            count.1 += right;
+        }
+
+        counts
+    }
+
+    pub fn side_disadvantage(&self) -> Option<&SideDisadvantage> {
+        self.side_disadvantage.as_ref()
+    }
+
+    /// every player's disadvantage from the sides they played on, the games
+    /// carried over from earlier sessions counted with the disadvantage of
+    /// the first period; all 0 without a `SideDisadvantage`
+    pub fn disadvantages(&self) -> BTreeMap<usize, u64> {
+        let mut disadvantages: BTreeMap<usize, u64> =
+            self.players.iter().map(|p| (*p, 0)).collect();
+        let Some(side_disadvantage) = &self.side_disadvantage else {
+            return disadvantages;
+        };
+
+        let (first_left, first_right) = side_disadvantage.at(0);
+        for (player, (left, right)) in self.carried_sides.iter() {
+            *disadvantages.entry(*player).or_default() += *left as u64
+                * u64::from(first_left)
+                + *right as u64 * u64::from(first_right);
+        }
+
+        for (played, m) in self.matches.iter().enumerate() {
+            let (left, right) = side_disadvantage.at(played);
+            *disadvantages.entry(m.left).or_default() += u64::from(left);
+            *disadvantages.entry(m.right).or_default() += u64::from(right);
+        }
+
+        disadvantages
+    }
+
+    /// how often the two players played against each other, on any side
+    pub fn pairing_count(&self, players: (usize, usize)) -> usize {
+        self.matches
+            .iter()
+            .filter(|m| m.check_same_players(players))
+            .count()
+    }
+
+    /// every ordered pairing of two different players, rules not applied
+    pub fn candidate_matches(&self) -> Vec<(usize, usize)> {
+        self.players
+            .iter()
+            .cartesian_product(self.players.iter())
+            .filter(|(left, right)| left != right)
+            .map(|(left, right)| (*left, *right))
+            .collect_vec()
+    }
+
+    /// the part of the history relevant for the rules, the pairings only if
+    /// rule 4 is kept
+    pub fn state(&self) -> TtState {
+        let recent = self.get_last_matches(self.get_rule_memory());
+
+        let pairings = if !self.rules().contains(Rule::SameSidesVsSameOpponent)
+        {
+            vec![]
+        } else {
+            let mut latest: BTreeMap<(usize, usize), TtMatch> = BTreeMap::new();
+            for m in self.matches.iter() {
+                let key = (m.left.min(m.right), m.left.max(m.right));
+                latest.insert(key, m.clone());
+            }
+            latest.into_values().sorted().collect_vec()
+        };
+
+        let rests = match self.max_rest {
+            Some(_) if self.rules.contains(Rule::MaxRest) => {
+                self.rests().into_iter().collect_vec()
+            }
+            _ => vec![],
+        };
+
+        TtState {
+            recent,
+            pairings,
+            rests,
+        }
+    }
+
+    /// the same playthrough with every player renamed according to `mapping`
+    pub fn relabeled(&self, mapping: &BTreeMap<usize, usize>) -> Self {
+        let mut players = self
+            .players
+            .iter()
+            .map(|p| *mapping.get(p).unwrap_or(p))
+            .collect_vec();
+        players.sort();
+
+        Self {
+            max_repeting_games_per_player: self.max_repeting_games_per_player,
+            default_games_limit: self.default_games_limit,
+            games_limits: self
+                .games_limits
+                .iter()
+                .map(|(p, limit)| (*mapping.get(p).unwrap_or(p), *limit))
+                .collect(),
+            max_rest: self.max_rest,
+            rules: self.rules.clone(),
+            carried_sides: self
+                .carried_sides
+                .iter()
+                .map(|(p, sides)| (*mapping.get(p).unwrap_or(p), *sides))
+                .collect(),
+            side_disadvantage: self.side_disadvantage.clone(),
+            players,
+            matches: self
+                .matches
+                .iter()
+                .map(|m| m.relabeled(mapping))
+                .collect(),
+        }
+    }
+
     //
     // public interface
     //
+    /// plays the match if both players take part and it doesn't break any
+    /// hard rule
+    pub fn play_match(
+        &mut self,
+        players: (usize, usize),
+    ) -> Result<(), TtError> {
+        self.check_players(players)?;
+
+        let broken_rules = self
+            .violated_rules(players)
+            .into_iter()
+            .filter(|rule| self.constraint(*rule) == Some(Constraint::Hard))
+            .collect_vec();
+        if !broken_rules.is_empty() {
+            return Err(TtError::RuleViolation {
+                players,
+                rules: broken_rules,
+            });
+        }
+
+        self.append_game(players.0, players.1);
+        Ok(())
+    }
+
+    /// Plays the match if it doesn't break any hard rule, skipping rule 4
+    /// if `allow_rule_4` is true and keeping it if false, no matter what the
+    /// playthrough's rules say.
+    #[deprecated(
+        note = "keep or drop rule 4 with `with_rule` or `without_rule` and \
+                use `play_match`"
+    )]
     pub fn play_match_if_possible(
         &mut self,
         players: (usize, usize),
         allow_rule_4: bool,
     ) {
-        if self.check_match_possible(players, allow_rule_4) {
-            self.append_game(players.0, players.1);
+        let rules = self.rules.clone();
+        let rule_4 = Rule::SameSidesVsSameOpponent;
+
+        if allow_rule_4 {
+            self.rules = rules.clone().without_rule(rule_4);
+        } else if !rules.contains(rule_4) {
+            self.rules = rules.clone().with_rule(rule_4);
         }
+
+        let _ = self.play_match(players);
+        self.rules = rules;
     }
 
-    pub fn check_match_possible(
+    /// plays the match without checking any rule, e.g. when a schedule
+    /// can't avoid breaking one or it was played regardless, only the
+    /// players have to be valid
+    pub fn force_match(
         &mut self,
         players: (usize, usize),
-        allow_rule_4: bool,
-    ) -> bool {
-        // Rule 1: don't play same players
-        !self.check_same_players_as_before(players)
+    ) -> Result<(), TtError> {
+        self.check_players(players)?;
+
+        self.append_game(players.0, players.1);
+        Ok(())
+    }
+
+    /// both players take part and they are different
+    fn check_players(&self, players: (usize, usize)) -> Result<(), TtError> {
+        for player in [players.0, players.1] {
+            if !self.players.contains(&player) {
+                return Err(TtError::UnknownPlayer(player));
+            }
+        }
+        if players.0 == players.1 {
+            return Err(TtError::SelfMatch(players.0));
+        }
+
+        Ok(())
+    }
+
+    /// a match is possible if it doesn't break any hard rule
+    pub fn check_match_possible(&self, players: (usize, usize)) -> bool {
+        self.rules.rules().all(|(rule, constraint)| {
+            constraint != Constraint::Hard || self.check_rule(rule, players)
+        })
+    }
+
+    /// sum of the weights of the soft rules the match breaks, 0 for a match
+    /// which keeps all rules
+    pub fn match_penalty(&self, players: (usize, usize)) -> u32 {
+        self.rules
+            .rules()
+            .map(|(rule, constraint)| match constraint {
+                Constraint::Soft(weight) if !self.check_rule(rule, players) => {
+                    weight
+                }
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// the kept rules the match breaks, hard or soft
+    pub fn violated_rules(&self, players: (usize, usize)) -> Vec<Rule> {
+        self.rules
+            .rules()
+            .map(|(rule, _)| rule)
+            .filter(|rule| !self.check_rule(*rule, players))
+            .collect_vec()
+    }
+
+    /// true if the match keeps the rule, whether the playthrough keeps the
+    /// rule or not
+    pub fn check_rule(&self, rule: Rule, players: (usize, usize)) -> bool {
+        match rule {
+            // Rule 1: don't play same players
+            Rule::SamePlayers => !self.check_same_players_as_before(players),
 
             // Rule 2: don't play three times in a row
-            && self.check_not_played_twice_before(players)
+            Rule::RepeatedGames => self.check_not_played_twice_before(players),
 
             // Rule 3: don't play on the same side of the table as in the game before
-            && self.check_not_on_same_side_as_one_game_before(players)
+            Rule::SameSide => {
+                self.check_not_on_same_side_as_one_game_before(players)
+            }
 
             // Rule 4: don't play on the same sides when facing the same opponent again
-            && (allow_rule_4 || self.check_not_on_same_sides_facing_the_same_opponent_again(players))
+            Rule::SameSidesVsSameOpponent => self
+                .check_not_on_same_sides_facing_the_same_opponent_again(
+                    players,
+                ),
+
+            // Rule 5: don't let anybody else sit out for too long
+            Rule::MaxRest => self.check_nobody_else_rests_too_long(players),
+        }
     }
 
     //
@@ -111,36 +887,37 @@ impl TtPlaythrough {
         }
     }
 
-    /// Rule 2: don't play three times in a row
+    /// Rule 2: don't play three times in a row, or whatever the players'
+    /// games limits allow
     fn check_not_played_twice_before(&self, players: (usize, usize)) -> bool {
-        let players_map = self.get_last_n_games_counts();
-
         log::debug!(
-            "Checking last {} games: {:?}",
-            self.max_repeting_games_per_player,
-            &players_map
+            "Checking {} for {} and {} for {}: {:?}",
+            self.games_limit(players.0),
+            players.0,
+            self.games_limit(players.1),
+            players.1,
+            &self.get_last_n_games_counts()
         );
 
-        if players_map.get(&players.0).unwrap_or(&0).to_owned()
-            >= self.max_repeting_games_per_player
-        {
-            log::debug!(
-                "Player {} played {} times in the last {} games already.",
-                players.0,
-                players_map.get(&players.0).unwrap_or(&0),
-                self.max_repeting_games_per_player,
-            );
-            return false;
-        }
+        self.check_games_limit(players.0) && self.check_games_limit(players.1)
+    }
 
-        if players_map.get(&players.1).unwrap_or(&0).to_owned()
-            >= self.max_repeting_games_per_player
-        {
+    fn check_games_limit(&self, player: usize) -> bool {
+        let limit = self.games_limit(player);
+        let looked_at = limit.window.saturating_sub(1);
+
+        let games = self
+            .get_last_matches(looked_at)
+            .iter()
+            .filter(|m| m.has_player(player))
+            .count();
+
+        if games >= limit.max_games {
             log::debug!(
                 "Player {} played {} times in the last {} games already.",
-                players.1,
-                players_map.get(&players.1).unwrap_or(&0),
-                self.max_repeting_games_per_player,
+                player,
+                games,
+                looked_at,
             );
             return false;
         }
@@ -149,6 +926,7 @@ impl TtPlaythrough {
     }
 
     /// Rule 3: don't play on the same side of the table as in the game before
+    #[allow(clippy::if_same_then_else)]
     fn check_not_on_same_side_as_one_game_before(
         &self,
         players: (usize, usize),
@@ -165,6 +943,7 @@ impl TtPlaythrough {
     }
 
     /// Rule 4: don't play on the same sides when facing the same opponent again
+    #[allow(clippy::if_same_then_else)]
     fn check_not_on_same_sides_facing_the_same_opponent_again(
         &self,
         players: (usize, usize),
@@ -186,6 +965,29 @@ impl TtPlaythrough {
         true
     }
 
+    /// Rule 5: don't let anybody else sit out for too long
+    fn check_nobody_else_rests_too_long(
+        &self,
+        players: (usize, usize),
+    ) -> bool {
+        let Some(max_rest) = self.max_rest else {
+            return true;
+        };
+
+        for (player, rest) in self.rests() {
+            if player != players.0 && player != players.1 && rest >= max_rest {
+                log::debug!(
+                    "Player {} sat out {} games already and has to play.",
+                    player,
+                    rest,
+                );
+                return false;
+            }
+        }
+
+        true
+    }
+
     fn get_last_n_games_counts(&self) -> BTreeMap<usize, usize> {
         let last_n_matches = self.get_last_n_matches();
         let mut players_map = self.get_empty_player_map();
@@ -206,17 +1008,22 @@ impl TtPlaythrough {
     }
 
     fn get_last_n_matches(&self) -> Vec<TtMatch> {
-        let mut last_n = self
-            .matches
-            .clone()
-            .into_iter()
-            .rev()
-            .take(self.max_repeting_games_per_player)
-            .collect_vec();
+        self.get_last_matches(self.max_repeting_games_per_player)
+    }
 
-        last_n.reverse();
+    fn get_last_matches(&self, n: usize) -> Vec<TtMatch> {
+        self.matches[self.matches.len().saturating_sub(n)..].to_vec()
+    }
 
-        last_n
+    /// number of past matches any of rules 1 to 3 looks at
+    fn get_rule_memory(&self) -> usize {
+        self.games_limits
+            .values()
+            .chain([&self.default_games_limit])
+            .map(|limit| limit.window.saturating_sub(1))
+            .max()
+            .unwrap_or(0)
+            .max(1)
     }
 
     fn get_matches_reversed(&self) -> Vec<TtMatch> {
@@ -242,7 +1049,110 @@ impl TtPlaythrough {
     }
 }
 
+/// Version of the schema playthroughs are saved with. Fields added later
+/// get defaults, so older saves keep loading, it's only bumped for changes
+/// which need a migration in `TryFrom<PlaythroughRecord>`.
+#[cfg(feature = "serde")]
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// how a playthrough is saved
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PlaythroughRecord {
+    #[serde(default = "first_schema_version")]
+    version: u32,
+    players: Vec<usize>,
+    max_repeting_games_per_player: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_games_limit: Option<GamesLimit>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    games_limits: Vec<(usize, GamesLimit)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_rest: Option<usize>,
+    #[serde(default)]
+    rules: RuleSet,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    carried_sides: Vec<(usize, (usize, usize))>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    side_disadvantage: Option<SideDisadvantage>,
+    #[serde(default)]
+    matches: Vec<TtMatch>,
+}
+
+#[cfg(feature = "serde")]
+fn first_schema_version() -> u32 {
+    1
+}
+
+#[cfg(feature = "serde")]
+impl From<TtPlaythrough> for PlaythroughRecord {
+    fn from(playthrough: TtPlaythrough) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            players: playthrough.players,
+            max_repeting_games_per_player: playthrough
+                .max_repeting_games_per_player,
+            default_games_limit: Some(playthrough.default_games_limit),
+            games_limits: playthrough.games_limits.into_iter().collect(),
+            max_rest: playthrough.max_rest,
+            rules: playthrough.rules,
+            carried_sides: playthrough.carried_sides.into_iter().collect(),
+            side_disadvantage: playthrough.side_disadvantage,
+            matches: playthrough.matches,
+        }
+    }
+}
+
+/// validates the saved playthrough like a new one, every match included
+#[cfg(feature = "serde")]
+impl TryFrom<PlaythroughRecord> for TtPlaythrough {
+    type Error = TtError;
+
+    fn try_from(record: PlaythroughRecord) -> Result<Self, TtError> {
+        if record.version > SCHEMA_VERSION {
+            return Err(TtError::InvalidConfiguration(format!(
+                "saved with schema version {}, only up to {SCHEMA_VERSION} \
+                 can be read",
+                record.version
+            )));
+        }
+
+        let mut playthrough = TtPlaythrough::new(
+            record.players,
+            record.max_repeting_games_per_player,
+        )?
+        .with_rules(record.rules);
+
+        if let Some(limit) = record.default_games_limit {
+            playthrough = playthrough.with_default_games_limit(limit)?;
+        }
+        for (player, limit) in record.games_limits {
+            playthrough = playthrough.with_games_limit(player, limit)?;
+        }
+        // not `with_max_rest`, that would keep rule 5 even if it was dropped
+        if let Some(max_rest) = record.max_rest {
+            check_max_rest(max_rest)?;
+        }
+        playthrough.max_rest = record.max_rest;
+
+        for (player, sides) in record.carried_sides {
+            playthrough = playthrough.with_carried_sides(player, sides)?;
+        }
+        if let Some(side_disadvantage) = &record.side_disadvantage {
+            side_disadvantage.check()?;
+        }
+        playthrough.side_disadvantage = record.side_disadvantage;
+
+        for m in record.matches {
+            playthrough.force_match((m.left, m.right))?;
+        }
+
+        Ok(playthrough)
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -262,7 +1172,7 @@ mod tests {
 
     #[test]
     fn test_get_empty_player_map() {
-        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         let empty_map = playthrough.get_empty_player_map();
         assert_eq!(empty_map, BTreeMap::from([(0, 0), (1, 0), (2, 0)]));
@@ -270,7 +1180,8 @@ mod tests {
 
     #[test]
     fn test_get_last_n_games() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         let last_n_matches = playthrough.get_last_n_matches();
         assert!(last_n_matches.is_empty());
@@ -301,7 +1212,8 @@ mod tests {
 
     #[test]
     fn test_get_last_n_games_counts() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         playthrough.append_game(0, 1);
         playthrough.append_game(0, 2);
@@ -328,7 +1240,8 @@ mod tests {
 
     #[test]
     fn test_get_matches_reversed() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         playthrough.append_game(0, 1);
         playthrough.append_game(0, 2);
@@ -352,7 +1265,8 @@ mod tests {
 
     #[test]
     fn test_check_same_players_as_before() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         playthrough.append_game(0, 1);
         playthrough.append_game(0, 2);
@@ -367,7 +1281,8 @@ mod tests {
 
     #[test]
     fn test_check_not_on_same_side_as_one_game_before() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         playthrough.append_game(0, 1);
         playthrough.append_game(0, 2);
@@ -400,7 +1315,8 @@ mod tests {
 
     #[test]
     fn test_check_not_on_same_sides_facing_the_same_opponent_again() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         playthrough.append_game(0, 1);
         playthrough.append_game(0, 2);
@@ -442,7 +1358,8 @@ mod tests {
 
     #[test]
     fn test_check_not_played_twice_before() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
         playthrough.append_game(0, 1);
         playthrough.append_game(0, 2);
@@ -464,75 +1381,413 @@ mod tests {
         assert_eq!(playthrough.check_not_played_twice_before((2, 1)), false);
     }
 
+    #[test]
+    fn test_check_games_limit_per_player() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .with_games_limit(0, GamesLimit::consecutive(1))
+            .unwrap();
+
+        playthrough.append_game(0, 1);
+
+        assert!(!playthrough.check_not_played_twice_before((2, 0)));
+        assert!(playthrough.check_not_played_twice_before((2, 1)));
+
+        playthrough.append_game(2, 1);
+
+        assert!(playthrough.check_not_played_twice_before((0, 3)));
+        assert!(!playthrough.check_not_played_twice_before((1, 3)));
+    }
+
+    #[test]
+    fn test_invalid_games_limits() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2], 2).unwrap();
+
+        for limit in [GamesLimit::consecutive(0), GamesLimit::within(2, 2)] {
+            assert!(matches!(
+                playthrough.clone().with_default_games_limit(limit),
+                Err(TtError::InvalidConfiguration(_))
+            ));
+            assert!(playthrough.clone().with_games_limit(0, limit).is_err());
+        }
+        assert_eq!(
+            playthrough
+                .clone()
+                .with_games_limit(3, GamesLimit::consecutive(1))
+                .unwrap_err(),
+            TtError::UnknownPlayer(3)
+        );
+        assert_eq!(
+            playthrough
+                .clone()
+                .with_carried_sides(3, (1, 0))
+                .unwrap_err(),
+            TtError::UnknownPlayer(3)
+        );
+        assert!(matches!(
+            playthrough.with_max_rest(0),
+            Err(TtError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_games_limit_within_window() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .with_default_games_limit(GamesLimit::within(2, 4))
+            .unwrap();
+
+        playthrough.append_game(0, 1);
+        playthrough.append_game(2, 3);
+        playthrough.append_game(1, 0);
+
+        // 0 and 1 already played 2 of the last 3 matches
+        assert!(!playthrough.check_not_played_twice_before((0, 2)));
+        assert!(!playthrough.check_not_played_twice_before((3, 1)));
+        assert!(playthrough.check_not_played_twice_before((2, 3)));
+
+        playthrough.append_game(2, 3);
+
+        // the first 0-1 dropped out of the window
+        assert!(playthrough.check_not_played_twice_before((1, 0)));
+        assert!(!playthrough.check_not_played_twice_before((2, 1)));
+    }
+
+    #[test]
+    fn test_rests() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2).unwrap();
+
+        playthrough.append_game(0, 1);
+        playthrough.append_game(2, 0);
+        playthrough.append_game(1, 2);
+
+        assert_eq!(
+            playthrough.rests(),
+            BTreeMap::from([(0, 1), (1, 0), (2, 0), (3, 3)])
+        );
+    }
+
+    #[test]
+    fn test_side_and_pairing_counts() {
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
+
+        playthrough.append_game(0, 1);
+        playthrough.append_game(2, 0);
+        playthrough.append_game(1, 0);
+
+        assert_eq!(
+            playthrough.side_counts(),
+            BTreeMap::from([(0, (1, 2)), (1, (1, 1)), (2, (1, 0))])
+        );
+        assert_eq!(playthrough.pairing_count((0, 1)), 2);
+        assert_eq!(playthrough.pairing_count((2, 0)), 1);
+        assert_eq!(playthrough.pairing_count((1, 2)), 0);
+    }
+
+    #[test]
+    fn test_check_nobody_else_rests_too_long() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .with_max_rest(2)
+            .unwrap();
+
+        playthrough.append_game(0, 1);
+        assert!(playthrough.check_nobody_else_rests_too_long((0, 2)));
+
+        playthrough.append_game(0, 2);
+
+        // 3 sat out two matches already
+        assert!(!playthrough.check_nobody_else_rests_too_long((1, 2)));
+        assert!(playthrough.check_nobody_else_rests_too_long((1, 3)));
+        assert!(playthrough.check_nobody_else_rests_too_long((3, 2)));
+    }
+
+    #[test]
+    fn test_soft_rules() {
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent)
+            .with_constraint(Rule::SamePlayers, Constraint::Soft(2))
+            .with_constraint(Rule::SameSide, Constraint::Soft(3));
+
+        playthrough.append_game(2, 1);
+
+        assert_eq!(playthrough.violated_rules((0, 1)), [Rule::SameSide]);
+        assert_eq!(
+            playthrough.violated_rules((2, 1)),
+            [Rule::SamePlayers, Rule::SameSide]
+        );
+        assert!(playthrough.check_match_possible((2, 1)));
+        assert_eq!(playthrough.match_penalty((2, 1)), 5);
+        assert_eq!(playthrough.match_penalty((1, 0)), 0);
+
+        playthrough.append_game(0, 2);
+
+        // rule 2 is still hard
+        assert_eq!(playthrough.violated_rules((2, 1)), [Rule::RepeatedGames]);
+        assert!(!playthrough.check_match_possible((2, 1)));
+    }
+
     #[test]
     fn test_check_matches_possible_with_rule_4() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough =
+            TtPlaythrough::new(TEST_PLAYERS.into(), 2).unwrap();
 
-        assert_eq!(playthrough.check_match_possible((0, 1), false), true);
-        assert_eq!(playthrough.check_match_possible((0, 2), false), true);
-        assert_eq!(playthrough.check_match_possible((1, 0), false), true);
-        assert_eq!(playthrough.check_match_possible((2, 0), false), true);
-        assert_eq!(playthrough.check_match_possible((1, 2), false), true);
-        assert_eq!(playthrough.check_match_possible((2, 1), false), true);
+        assert_eq!(playthrough.check_match_possible((0, 1)), true);
+        assert_eq!(playthrough.check_match_possible((0, 2)), true);
+        assert_eq!(playthrough.check_match_possible((1, 0)), true);
+        assert_eq!(playthrough.check_match_possible((2, 0)), true);
+        assert_eq!(playthrough.check_match_possible((1, 2)), true);
+        assert_eq!(playthrough.check_match_possible((2, 1)), true);
 
         playthrough.append_game(2, 1);
-        assert_eq!(playthrough.check_match_possible((0, 1), false), false); // same side
-        assert_eq!(playthrough.check_match_possible((0, 2), false), true);
-        assert_eq!(playthrough.check_match_possible((1, 0), false), true);
-        assert_eq!(playthrough.check_match_possible((2, 0), false), false); // same side
-        assert_eq!(playthrough.check_match_possible((1, 2), false), false); // same players
-        assert_eq!(playthrough.check_match_possible((2, 1), false), false); // same players
+        assert_eq!(playthrough.check_match_possible((0, 1)), false); // same side
+        assert_eq!(playthrough.check_match_possible((0, 2)), true);
+        assert_eq!(playthrough.check_match_possible((1, 0)), true);
+        assert_eq!(playthrough.check_match_possible((2, 0)), false); // same side
+        assert_eq!(playthrough.check_match_possible((1, 2)), false); // same players
+        assert_eq!(playthrough.check_match_possible((2, 1)), false); // same players
 
         playthrough.append_game(0, 2);
-        assert_eq!(playthrough.check_match_possible((0, 1), false), false); // same side
-        assert_eq!(playthrough.check_match_possible((0, 2), false), false); // same players
-        assert_eq!(playthrough.check_match_possible((1, 0), false), true);
-        assert_eq!(playthrough.check_match_possible((2, 0), false), false); // same players
-        assert_eq!(playthrough.check_match_possible((1, 2), false), false); // same side
-        assert_eq!(playthrough.check_match_possible((2, 1), false), false); // played twice already
+        assert_eq!(playthrough.check_match_possible((0, 1)), false); // same side
+        assert_eq!(playthrough.check_match_possible((0, 2)), false); // same players
+        assert_eq!(playthrough.check_match_possible((1, 0)), true);
+        assert_eq!(playthrough.check_match_possible((2, 0)), false); // same players
+        assert_eq!(playthrough.check_match_possible((1, 2)), false); // same side
+        assert_eq!(playthrough.check_match_possible((2, 1)), false); // played twice already
 
         playthrough.append_game(1, 0);
-        assert_eq!(playthrough.check_match_possible((0, 1), false), false); // same players
-        assert_eq!(playthrough.check_match_possible((0, 2), false), false); // played twice already
-        assert_eq!(playthrough.check_match_possible((1, 0), false), false); // same players
-        assert_eq!(playthrough.check_match_possible((2, 0), false), false); // played twice already
-        assert_eq!(playthrough.check_match_possible((1, 2), false), false); // same side
-        assert_eq!(playthrough.check_match_possible((2, 1), false), false); // same side facing same opponent
+        assert_eq!(playthrough.check_match_possible((0, 1)), false); // same players
+        assert_eq!(playthrough.check_match_possible((0, 2)), false); // played twice already
+        assert_eq!(playthrough.check_match_possible((1, 0)), false); // same players
+        assert_eq!(playthrough.check_match_possible((2, 0)), false); // played twice already
+        assert_eq!(playthrough.check_match_possible((1, 2)), false); // same side
+        assert_eq!(playthrough.check_match_possible((2, 1)), false); // same side facing same opponent
     }
 
     #[test]
     fn test_check_matches_possible_without_rule_4() {
-        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2);
+        let mut playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
 
-        assert_eq!(playthrough.check_match_possible((0, 1), true), true);
-        assert_eq!(playthrough.check_match_possible((0, 2), true), true);
-        assert_eq!(playthrough.check_match_possible((1, 0), true), true);
-        assert_eq!(playthrough.check_match_possible((2, 0), true), true);
-        assert_eq!(playthrough.check_match_possible((1, 2), true), true);
-        assert_eq!(playthrough.check_match_possible((2, 1), true), true);
+        assert_eq!(playthrough.check_match_possible((0, 1)), true);
+        assert_eq!(playthrough.check_match_possible((0, 2)), true);
+        assert_eq!(playthrough.check_match_possible((1, 0)), true);
+        assert_eq!(playthrough.check_match_possible((2, 0)), true);
+        assert_eq!(playthrough.check_match_possible((1, 2)), true);
+        assert_eq!(playthrough.check_match_possible((2, 1)), true);
 
         playthrough.append_game(2, 1);
-        assert_eq!(playthrough.check_match_possible((0, 1), true), false); // same side
-        assert_eq!(playthrough.check_match_possible((0, 2), true), true);
-        assert_eq!(playthrough.check_match_possible((1, 0), true), true);
-        assert_eq!(playthrough.check_match_possible((2, 0), true), false); // same side
-        assert_eq!(playthrough.check_match_possible((1, 2), true), false); // same players
-        assert_eq!(playthrough.check_match_possible((2, 1), true), false); // same players
+        assert_eq!(playthrough.check_match_possible((0, 1)), false); // same side
+        assert_eq!(playthrough.check_match_possible((0, 2)), true);
+        assert_eq!(playthrough.check_match_possible((1, 0)), true);
+        assert_eq!(playthrough.check_match_possible((2, 0)), false); // same side
+        assert_eq!(playthrough.check_match_possible((1, 2)), false); // same players
+        assert_eq!(playthrough.check_match_possible((2, 1)), false); // same players
 
         playthrough.append_game(0, 2);
-        assert_eq!(playthrough.check_match_possible((0, 1), true), false); // same side
-        assert_eq!(playthrough.check_match_possible((0, 2), true), false); // same players
-        assert_eq!(playthrough.check_match_possible((1, 0), true), true);
-        assert_eq!(playthrough.check_match_possible((2, 0), true), false); // same players
-        assert_eq!(playthrough.check_match_possible((1, 2), true), false); // same side
-        assert_eq!(playthrough.check_match_possible((2, 1), true), false); // played twice already
+        assert_eq!(playthrough.check_match_possible((0, 1)), false); // same side
+        assert_eq!(playthrough.check_match_possible((0, 2)), false); // same players
+        assert_eq!(playthrough.check_match_possible((1, 0)), true);
+        assert_eq!(playthrough.check_match_possible((2, 0)), false); // same players
+        assert_eq!(playthrough.check_match_possible((1, 2)), false); // same side
+        assert_eq!(playthrough.check_match_possible((2, 1)), false); // played twice already
 
         playthrough.append_game(1, 0);
-        assert_eq!(playthrough.check_match_possible((0, 1), true), false); // same players
-        assert_eq!(playthrough.check_match_possible((0, 2), true), false); // played twice already
-        assert_eq!(playthrough.check_match_possible((1, 0), true), false); // same players
-        assert_eq!(playthrough.check_match_possible((2, 0), true), false); // played twice already
-        assert_eq!(playthrough.check_match_possible((1, 2), true), false); // same side
-        assert_eq!(playthrough.check_match_possible((2, 1), true), true);
+        assert_eq!(playthrough.check_match_possible((0, 1)), false); // same players
+        assert_eq!(playthrough.check_match_possible((0, 2)), false); // played twice already
+        assert_eq!(playthrough.check_match_possible((1, 0)), false); // same players
+        assert_eq!(playthrough.check_match_possible((2, 0)), false); // played twice already
+        assert_eq!(playthrough.check_match_possible((1, 2)), false); // same side
+        assert_eq!(playthrough.check_match_possible((2, 1)), true);
+    }
+
+    #[test]
+    fn test_rule_set() {
+        let rules = RuleSet::default();
+        assert!(rules.contains(Rule::SameSidesVsSameOpponent));
+        assert!(!rules.contains(Rule::MaxRest));
+
+        let rules = rules
+            .without_rule(Rule::SameSidesVsSameOpponent)
+            .with_constraint(Rule::SameSide, Constraint::Soft(3));
+        assert_eq!(rules.constraint(Rule::SameSidesVsSameOpponent), None);
+        assert_eq!(rules.constraint(Rule::SameSide), Some(Constraint::Soft(3)));
+        assert_eq!(
+            rules.rules().map(|(rule, _)| rule.number()).collect_vec(),
+            [1, 2, 3]
+        );
+
+        let playthrough = TtPlaythrough::new(TEST_PLAYERS.into(), 2)
+            .unwrap()
+            .with_max_rest(1)
+            .unwrap();
+        assert_eq!(
+            playthrough.constraint(Rule::MaxRest),
+            Some(Constraint::Hard)
+        );
+    }
+
+    #[test]
+    fn test_play_match() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2).unwrap();
+
+        assert_eq!(playthrough.play_match((0, 1)), Ok(()));
+        assert_eq!(playthrough.play_match((2, 3)), Ok(()));
+        assert_eq!(
+            playthrough.play_match((0, 1)),
+            Err(TtError::RuleViolation {
+                players: (0, 1),
+                rules: vec![Rule::SameSidesVsSameOpponent],
+            })
+        );
+        assert_eq!(playthrough.play_match((1, 0)), Ok(()));
+
+        assert_eq!(playthrough.play_match((2, 2)), Err(TtError::SelfMatch(2)));
+        assert_eq!(
+            playthrough.force_match((3, 7)),
+            Err(TtError::UnknownPlayer(7))
+        );
+        assert_eq!(playthrough.matches().len(), 3);
+    }
+
+    #[test]
+    fn test_new_validates() {
+        let error = |players: Vec<usize>, max_games| {
+            TtPlaythrough::new(players, max_games)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error(vec![0], 2),
+            "invalid configuration: at least 2 players are needed"
+        );
+        assert_eq!(
+            error(vec![0, 1, 1], 2),
+            "invalid configuration: player 1 is listed twice"
+        );
+        assert_eq!(
+            error(vec![0, 1], 0),
+            "invalid configuration: max_repeting_games_per_player must be at \
+             least 1"
+        );
+    }
+
+    #[test]
+    fn test_disadvantages() {
+        // the right side gets sun glare after two matches
+        let side_disadvantage = SideDisadvantage::new(1, 0)
+            .after_matches(2, 1, 5)
+            .after_matches(2, 1, 4);
+        assert_eq!(side_disadvantage.at(0), (1, 0));
+        assert_eq!(side_disadvantage.at(1), (1, 0));
+        assert_eq!(side_disadvantage.at(2), (1, 4));
+        assert_eq!(side_disadvantage.at(9), (1, 4));
+
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2], 2)
+            .unwrap()
+            .with_carried_sides(2, (3, 1))
+            .unwrap();
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((1, 2)).unwrap();
+        playthrough.play_match((2, 0)).unwrap();
+        assert!(playthrough.disadvantages().values().all(|d| *d == 0));
+
+        let playthrough = playthrough.with_side_disadvantage(side_disadvantage);
+        assert_eq!(
+            playthrough.disadvantages(),
+            BTreeMap::from([(0, 1 + 4), (1, 1), (2, 3 + 1)])
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_play_match_if_possible() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2).unwrap();
+        playthrough.play_match_if_possible((0, 1), false);
+        playthrough.play_match_if_possible((2, 3), false);
+
+        // true skips rule 4 for this match only
+        playthrough.play_match_if_possible((0, 1), false);
+        assert_eq!(playthrough.matches().len(), 2);
+        playthrough.play_match_if_possible((0, 1), true);
+        assert_eq!(playthrough.matches().len(), 3);
+        assert!(playthrough.rules().contains(Rule::SameSidesVsSameOpponent));
+
+        // false keeps rule 4 even if the playthrough dropped it
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        playthrough.play_match_if_possible((0, 1), false);
+        playthrough.play_match_if_possible((2, 3), false);
+        playthrough.play_match_if_possible((0, 1), false);
+        assert_eq!(playthrough.matches().len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        let mut playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .with_games_limit(3, GamesLimit::consecutive(1))
+            .unwrap()
+            .with_constraint(Rule::SameSide, Constraint::Soft(3))
+            .with_max_rest(2)
+            .unwrap()
+            .without_rule(Rule::MaxRest)
+            .with_side_disadvantage(SideDisadvantage::new(0, 1));
+        playthrough.play_match((0, 1)).unwrap();
+        playthrough.play_match((2, 3)).unwrap();
+
+        let json = serde_json::to_string(&playthrough).unwrap();
+        assert!(json.starts_with("{\"version\":1,"));
+        assert!(json.contains("\"same_side\":{\"soft\":3}"));
+
+        let loaded: TtPlaythrough = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.matches(), playthrough.matches());
+        assert_eq!(loaded.rules(), playthrough.rules());
+        assert_eq!(loaded.games_limit(3), GamesLimit::consecutive(1));
+        assert_eq!(loaded.state(), playthrough.state());
+        assert_eq!(loaded.disadvantages(), playthrough.disadvantages());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_loads_minimal_and_rejects_newer_versions() {
+        // no version and only the fields of the very first playthroughs
+        let loaded: TtPlaythrough = serde_json::from_str(
+            r#"{"players": [0, 1, 2], "max_repeting_games_per_player": 2,
+                "matches": [{"left": 0, "right": 1}]}"#,
+        )
+        .unwrap();
+        assert_eq!(loaded.matches(), &[TtMatch::new(0, 1)]);
+        assert_eq!(loaded.rules(), &RuleSet::default());
+
+        let error = serde_json::from_str::<TtPlaythrough>(
+            r#"{"version": 2, "players": [0, 1],
+                "max_repeting_games_per_player": 2}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("schema version 2"));
+
+        let error = serde_json::from_str::<TtPlaythrough>(
+            r#"{"players": [0, 1], "max_repeting_games_per_player": 2,
+                "matches": [{"left": 0, "right": 5}]}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("player 5 doesn't take part"));
+
+        for periods in ["[]", "[[1, [1, 0]]]", "[[0, [1, 0]], [0, [0, 1]]]"] {
+            let json = format!(
+                r#"{{"players": [0, 1], "max_repeting_games_per_player": 2,
+                    "side_disadvantage": {{"periods": {periods}}}}}"#
+            );
+            let error =
+                serde_json::from_str::<TtPlaythrough>(&json).unwrap_err();
+            assert!(error.to_string().contains("periods"), "{periods}");
+        }
     }
 }
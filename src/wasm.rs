@@ -0,0 +1,150 @@
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use wasm_bindgen::prelude::*;
+
+use crate::generate;
+use crate::recommend::RecommendWeights;
+use crate::tt::{Rule, TtPlaythrough};
+
+/// A playthrough for JavaScript, e.g.
+///
+/// ```js
+/// const scheduler = new Scheduler(4, 2);
+/// scheduler.withoutRule(4);
+/// scheduler.playMatch(0, 1);
+/// const [left, right] = scheduler.nextMatch();
+/// ```
+///
+/// Matches go in and out as `left, right` pairs of player numbers, longer
+/// results as JSON.
+#[wasm_bindgen]
+pub struct Scheduler {
+    playthrough: TtPlaythrough,
+}
+
+#[wasm_bindgen]
+impl Scheduler {
+    /// players `0..players`, each playing at most `max_games` in a row
+    #[wasm_bindgen(constructor)]
+    pub fn new(players: usize, max_games: usize) -> Result<Scheduler, JsError> {
+        let playthrough =
+            TtPlaythrough::new((0..players).collect(), max_games)?;
+
+        Ok(Self { playthrough })
+    }
+
+    /// a playthrough saved with `toJson` or by the other front ends
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Scheduler, JsError> {
+        let playthrough = serde_json::from_str(json)?;
+
+        Ok(Self { playthrough })
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.playthrough)
+            .expect("a playthrough serializes")
+    }
+
+    /// keeps the rule of the given number as a hard rule
+    #[wasm_bindgen(js_name = withRule)]
+    pub fn with_rule(&mut self, number: usize) -> Result<(), JsError> {
+        let rule = rule(number)?;
+        self.playthrough = self.playthrough.clone().with_rule(rule);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = withoutRule)]
+    pub fn without_rule(&mut self, number: usize) -> Result<(), JsError> {
+        let rule = rule(number)?;
+        self.playthrough = self.playthrough.clone().without_rule(rule);
+
+        Ok(())
+    }
+
+    /// enables rule 5, see `TtPlaythrough::with_max_rest`
+    #[wasm_bindgen(js_name = setMaxRest)]
+    pub fn set_max_rest(&mut self, max_rest: usize) -> Result<(), JsError> {
+        self.playthrough = self.playthrough.clone().with_max_rest(max_rest)?;
+
+        Ok(())
+    }
+
+    /// throws the reason if the match isn't allowed
+    #[wasm_bindgen(js_name = playMatch)]
+    pub fn play_match(
+        &mut self,
+        left: usize,
+        right: usize,
+    ) -> Result<(), JsError> {
+        Ok(self.playthrough.play_match((left, right))?)
+    }
+
+    #[wasm_bindgen(js_name = isPossible)]
+    pub fn is_possible(&self, left: usize, right: usize) -> bool {
+        left != right
+            && [left, right]
+                .iter()
+                .all(|player| self.playthrough.players().contains(player))
+            && self.playthrough.check_match_possible((left, right))
+    }
+
+    /// takes back the last match, false if there was none
+    pub fn undo(&mut self) -> bool {
+        self.playthrough.undo_match().is_some()
+    }
+
+    /// all matches so far as `[left, right, left, right, ...]`
+    pub fn matches(&self) -> Vec<usize> {
+        self.playthrough
+            .matches()
+            .iter()
+            .flat_map(|m| [m.left(), m.right()])
+            .collect()
+    }
+
+    /// `[left, right]` of the top recommendation, empty if no match is
+    /// possible
+    #[wasm_bindgen(js_name = nextMatch)]
+    pub fn next_match(&self) -> Vec<usize> {
+        pair(generate::next_match_fairest(
+            &self.playthrough,
+            &RecommendWeights::default(),
+        ))
+    }
+
+    /// `[left, right]` of a match preferring the players who sat out the
+    /// longest, ties are broken by a generator seeded with `seed`
+    #[wasm_bindgen(js_name = nextMostRested)]
+    pub fn next_most_rested(&self, seed: u32) -> Vec<usize> {
+        let mut rng = SmallRng::seed_from_u64(seed.into());
+
+        pair(generate::next_match_most_rested(
+            &self.playthrough,
+            &mut rng,
+        ))
+    }
+
+    /// every possible next match with its scores, see
+    /// `TtPlaythrough::recommend_next_matches`
+    #[wasm_bindgen(js_name = recommendationsJson)]
+    pub fn recommendations_json(&self) -> String {
+        let recommendations = self
+            .playthrough
+            .recommend_next_matches(&RecommendWeights::default());
+
+        serde_json::to_string(&recommendations)
+            .expect("recommendations serialize")
+    }
+}
+
+fn rule(number: usize) -> Result<Rule, JsError> {
+    Rule::from_number(number)
+        .ok_or_else(|| JsError::new(&format!("there is no rule {number}")))
+}
+
+fn pair(players: Option<(usize, usize)>) -> Vec<usize> {
+    players.map_or(vec![], |(left, right)| vec![left, right])
+}
@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+
+use itertools::Itertools;
+
+use crate::round_robin::Violation;
+use crate::tt::{TtMatch, TtPlaythrough};
+
+/// "Winner stays" at the table: the winner of a match plays the next
+/// challenger from the queue, the loser lines up at the end of it.
+///
+/// The playthrough's rules still apply, so the winner switches sides (rule 3)
+/// and has to leave the table as well once rule 2 doesn't allow another game.
+/// A match is only added to the playthrough once its result is recorded.
+#[derive(Debug, Clone)]
+pub struct WinnerStays {
+    playthrough: TtPlaythrough,
+    queue: VecDeque<usize>,
+    current: TtMatch,
+    violations: Vec<Violation>,
+}
+
+impl WinnerStays {
+    /// the players line up in the order of `TtPlaythrough::players`, the
+    /// first two playing the first match
+    pub fn new(playthrough: TtPlaythrough) -> Self {
+        let mut queue: VecDeque<usize> =
+            playthrough.players().iter().copied().collect();
+        let current = next_from_queue(&playthrough, &mut queue);
+        let mut winner_stays = Self {
+            playthrough,
+            queue,
+            current,
+            violations: vec![],
+        };
+
+        winner_stays.check_current();
+
+        winner_stays
+    }
+
+    pub fn current_match(&self) -> &TtMatch {
+        &self.current
+    }
+
+    /// players waiting for their turn, the next challenger first
+    pub fn queue(&self) -> &VecDeque<usize> {
+        &self.queue
+    }
+
+    /// the matches played so far, without the current one
+    pub fn playthrough(&self) -> &TtPlaythrough {
+        &self.playthrough
+    }
+
+    /// The matches which break rules because no pair in the queue could
+    /// avoid it, the current match included. The index is the match's
+    /// position in the playthrough once it is played.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Ends the current match and sets up the next one, which is returned.
+    /// Returns `None` and changes nothing if `winner` doesn't play the
+    /// current match.
+    pub fn record_result(&mut self, winner: usize) -> Option<&TtMatch> {
+        if !self.current.has_player(winner) {
+            return None;
+        }
+
+        let players = (self.current.left(), self.current.right());
+        self.playthrough
+            .force_match(players)
+            .expect("the queue holds the playthrough's players");
+
+        let loser = if self.current.left() == winner {
+            self.current.right()
+        } else {
+            self.current.left()
+        };
+        self.queue.push_back(loser);
+
+        self.current = match self.next_with_winner(winner) {
+            Some(next) => next,
+            None => {
+                log::debug!("Player {} has to leave the table", winner);
+                self.queue.push_back(winner);
+                next_from_queue(&self.playthrough, &mut self.queue)
+            }
+        };
+        self.check_current();
+
+        Some(&self.current)
+    }
+
+    /// keeps the current match as a violation if it breaks rules
+    fn check_current(&mut self) {
+        let rules = self
+            .playthrough
+            .violated_rules((self.current.left(), self.current.right()));
+
+        if !rules.is_empty() {
+            self.violations.push(Violation {
+                index: self.playthrough.matches().len(),
+                tt_match: self.current.clone(),
+                rules,
+            });
+        }
+    }
+
+    /// the winner against the first challenger in the queue the rules allow
+    fn next_with_winner(&mut self, winner: usize) -> Option<TtMatch> {
+        let position = self.queue.iter().position(|challenger| {
+            possible_orientation(&self.playthrough, winner, *challenger)
+                .is_some()
+        })?;
+        let challenger = self.queue.remove(position)?;
+
+        possible_orientation(&self.playthrough, winner, challenger)
+    }
+}
+
+/// the first two players in the queue the rules allow to play, or the first
+/// two anyway if no pair of them may play
+fn next_from_queue(
+    playthrough: &TtPlaythrough,
+    queue: &mut VecDeque<usize>,
+) -> TtMatch {
+    let pairs = (0..queue.len()).tuple_combinations().collect_vec();
+
+    for (first, second) in pairs {
+        let (a, b) = (queue[first], queue[second]);
+
+        if let Some(next) = possible_orientation(playthrough, a, b) {
+            queue.remove(second);
+            queue.remove(first);
+            return next;
+        }
+    }
+
+    log::debug!("No match from the queue keeps the rules");
+    let a = queue.pop_front().expect("at least two players");
+    let b = queue.pop_front().expect("at least two players");
+
+    TtMatch::new(a, b)
+}
+
+/// `a` on the left if the rules allow it, else on the right
+fn possible_orientation(
+    playthrough: &TtPlaythrough,
+    a: usize,
+    b: usize,
+) -> Option<TtMatch> {
+    [(a, b), (b, a)]
+        .into_iter()
+        .find(|players| playthrough.check_match_possible(*players))
+        .map(|(left, right)| TtMatch::new(left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tt::Rule;
+
+    #[test]
+    fn test_winner_stays_and_switches_sides() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut winner_stays = WinnerStays::new(playthrough);
+
+        assert_eq!(winner_stays.current_match(), &TtMatch::new(0, 1));
+        assert_eq!(winner_stays.queue(), &[2, 3]);
+
+        // 0 played left and stays, now on the right
+        let next = winner_stays.record_result(0).cloned();
+        assert_eq!(next, Some(TtMatch::new(2, 0)));
+        assert_eq!(winner_stays.queue(), &[3, 1]);
+    }
+
+    #[test]
+    fn test_winner_has_to_leave_after_two_games() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2, 3], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut winner_stays = WinnerStays::new(playthrough);
+
+        winner_stays.record_result(0);
+        let next = winner_stays.record_result(0).cloned();
+
+        // a third game in a row breaks rule 2
+        assert_eq!(next, Some(TtMatch::new(3, 1)));
+        assert_eq!(winner_stays.queue(), &[2, 0]);
+    }
+
+    #[test]
+    fn test_record_result_of_somebody_not_playing() {
+        let playthrough = TtPlaythrough::new(vec![0, 1, 2], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut winner_stays = WinnerStays::new(playthrough);
+
+        assert_eq!(winner_stays.record_result(2), None);
+        assert_eq!(winner_stays.current_match(), &TtMatch::new(0, 1));
+        assert!(winner_stays.playthrough().matches().is_empty());
+
+        winner_stays.record_result(1);
+        assert_eq!(winner_stays.playthrough().matches(), [TtMatch::new(0, 1)]);
+    }
+
+    #[test]
+    fn test_two_players_report_the_broken_rules() {
+        let playthrough = TtPlaythrough::new(vec![0, 1], 2)
+            .unwrap()
+            .without_rule(Rule::SameSidesVsSameOpponent);
+        let mut winner_stays = WinnerStays::new(playthrough);
+        assert!(winner_stays.violations().is_empty());
+
+        winner_stays.record_result(0);
+        let violation = &winner_stays.violations()[0];
+        assert_eq!(violation.index, 1);
+        assert_eq!(&violation.tt_match, winner_stays.current_match());
+        assert!(violation.rules.contains(&Rule::SamePlayers));
+    }
+}
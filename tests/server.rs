@@ -0,0 +1,92 @@
+#![cfg(feature = "server")]
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::{env, fs, process, thread};
+
+use tiny_http::Server;
+use tttable::server::{self, Session};
+use tttable::TtPlaythrough;
+
+/// starts a server for a new session on a free local port
+fn start(name: &str) -> SocketAddr {
+    let path = env::temp_dir()
+        .join(format!("tttable-it-{}-{name}.json", process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut session =
+        Session::open(path, || TtPlaythrough::new(vec![0, 1, 2], 2).unwrap())
+            .unwrap();
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let address = server.server_addr().to_ip().unwrap();
+
+    thread::spawn(move || server::serve(&server, &mut session));
+
+    address
+}
+
+/// a minimal HTTP/1.1 client, returns the status and the body
+fn request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    body: &str,
+) -> (u16, String) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+
+    (status, body.to_string())
+}
+
+#[test]
+fn test_play_through_the_api() {
+    let address = start("play");
+
+    let (status, next) = request(address, "GET", "/next", "");
+    assert_eq!(status, 200);
+    assert!(next.starts_with(r#"{"players":[0,1],"#));
+
+    let (status, _) =
+        request(address, "POST", "/matches", r#"{"left":0,"right":1}"#);
+    assert_eq!(status, 200);
+    let (status, body) =
+        request(address, "POST", "/matches", r#"{"left":2,"right":2}"#);
+    assert_eq!(status, 422);
+    assert_eq!(
+        body,
+        r#"{"error":"player 2 can't play against themselves"}"#
+    );
+
+    let (status, state) = request(address, "GET", "/state", "");
+    assert_eq!(status, 200);
+    assert!(state.contains(r#""matches":[{"left":0,"right":1}]"#));
+
+    let (status, report) = request(address, "GET", "/report", "");
+    assert_eq!(status, 200);
+    assert!(report.contains("| 1 | 0 | 1 |"));
+
+    let (status, undone) = request(address, "POST", "/undo", "");
+    assert_eq!((status, undone.as_str()), (200, r#"{"left":0,"right":1}"#));
+    assert_eq!(request(address, "POST", "/undo", "").0, 409);
+}
+
+#[test]
+fn test_unknown_endpoint() {
+    let address = start("unknown");
+
+    let (status, body) = request(address, "DELETE", "/state", "");
+    assert_eq!(status, 404);
+    assert_eq!(body, r#"{"error":"unknown endpoint"}"#);
+}
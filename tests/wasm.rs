@@ -0,0 +1,37 @@
+//! Runs headless under Node.js: `wasm-pack test --node --features wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use tttable::wasm::Scheduler;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_schedule_in_wasm() {
+    let mut scheduler = Scheduler::new(3, 2).unwrap();
+
+    scheduler.play_match(0, 1).unwrap();
+    assert!(!scheduler.is_possible(1, 0)); // rule 1
+    assert!(scheduler.play_match(2, 2).is_err());
+
+    let next = scheduler.next_match();
+    assert_eq!(next.len(), 2);
+    assert!(next.contains(&2));
+    assert_eq!(scheduler.next_most_rested(7), scheduler.next_most_rested(7));
+
+    let saved = Scheduler::from_json(&scheduler.to_json()).unwrap();
+    assert_eq!(saved.matches(), vec![0, 1]);
+
+    assert!(scheduler.undo());
+    assert!(scheduler.matches().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_rules_by_number() {
+    let mut scheduler = Scheduler::new(4, 2).unwrap();
+    scheduler.without_rule(4).unwrap();
+    assert!(scheduler.without_rule(9).is_err());
+
+    scheduler.play_match(0, 1).unwrap();
+    scheduler.play_match(2, 3).unwrap();
+    // the same sides against the same opponent only break rule 4
+    assert!(scheduler.is_possible(0, 1));
+}
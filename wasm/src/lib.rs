@@ -0,0 +1,5 @@
+//! The WebAssembly package of `tttable`, built with
+//! `wasm-pack build --target web wasm`. The JavaScript API itself lives in
+//! `tttable::wasm`, this crate only links it into a `cdylib`.
+
+pub use tttable::wasm::*;